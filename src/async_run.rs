@@ -0,0 +1,49 @@
+use crate::{MyDrone, StepOutcome};
+use std::time::Duration;
+
+/// How long an idle drone waits before polling its channels again, doubled every time it finds
+/// nothing to do up to `MAX_IDLE_POLL_INTERVAL`
+const MIN_IDLE_POLL_INTERVAL: Duration = Duration::from_micros(50);
+
+/// Longest time an idle drone run by `MyDrone::run_async` waits before polling its channels again,
+/// and so the longest extra latency it adds to a packet
+pub const MAX_IDLE_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+impl MyDrone {
+    /// Same as `Drone::run`, but as a future which never blocks the thread of the runtime, so that
+    /// many drones can run on a small tokio thread pool.
+    ///
+    /// The channels of the drone are crossbeam channels which cannot be awaited, so the drone
+    /// processes everything ready with `MyDrone::step`, yielding to the runtime after each step, and
+    /// when there is nothing to do it sleeps for a while before polling them again, longer and longer
+    /// up to `MAX_IDLE_POLL_INTERVAL` until something arrives.
+    ///
+    /// # Panics
+    /// Same as `Drone::run`
+    pub async fn run_async(&mut self) {
+        let mut idle_wait = MIN_IDLE_POLL_INTERVAL;
+        loop {
+            let outcome = self.step();
+            // a timer leaving congested queues behind means the drone is waiting for its
+            // neighbors, it would only retry them again and again
+            let idle = match outcome {
+                StepOutcome::Finished => break,
+                StepOutcome::Idle => true,
+                StepOutcome::Timer => self.is_congested(),
+                _ => false,
+            };
+            if idle {
+                let now = self.clock.now();
+                let wait = match self.next_deadline() {
+                    Some(deadline) => idle_wait.min(deadline.saturating_duration_since(now)),
+                    None => idle_wait,
+                };
+                tokio::time::sleep(wait).await;
+                idle_wait = (idle_wait * 2).min(MAX_IDLE_POLL_INTERVAL);
+            } else {
+                idle_wait = MIN_IDLE_POLL_INTERVAL;
+                tokio::task::yield_now().await;
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{DroneError, MyDrone};
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType, FRAGMENT_DSIZE};
+
+/// Bytes counted for every packet on top of the data of the fragments, standing for its header
+pub const PACKET_HEADER_OVERHEAD: u32 = 32;
+
+/// Size of the biggest packet, a full fragment
+pub const MAX_PACKET_SIZE: u32 = PACKET_HEADER_OVERHEAD + FRAGMENT_DSIZE as u32;
+
+/// Size of a packet as seen by the bandwidth limit: the header overhead plus the `length` of the
+/// fragment, if it is one
+#[must_use]
+pub fn packet_size(packet: &Packet) -> u32 {
+    match &packet.pack_type {
+        PacketType::MsgFragment(fragment) => PACKET_HEADER_OVERHEAD + u32::from(fragment.length),
+        _ => PACKET_HEADER_OVERHEAD,
+    }
+}
+
+/// What the drone does with the packets sent faster than the bandwidth of a link
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExcessPolicy {
+    /// Keeps them in the outbound queue until there is bandwidth for them, the queue overflows
+    /// according to its `OverflowPolicy`
+    #[default]
+    Queue,
+    /// Drops them right away
+    Drop,
+}
+
+/// Bandwidth of the link to a neighbor, as a token bucket: every packet sent takes as many tokens
+/// as its `packet_size`, and tokens come back at `bytes_per_second` up to `burst` of them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinkBandwidth {
+    bytes_per_second: f64,
+    burst: u32,
+    excess: ExcessPolicy,
+}
+
+impl LinkBandwidth {
+    /// Limits the link to `bytes_per_second`, queueing the excess traffic
+    /// # Errors
+    /// Returns `DroneError::InvalidBandwidth` if `bytes_per_second` is not a positive finite
+    /// number or if `burst` is smaller than `MAX_PACKET_SIZE`, since some packets could never be
+    /// sent
+    pub fn new(bytes_per_second: f64, burst: u32) -> Result<Self, DroneError> {
+        if !bytes_per_second.is_finite() || bytes_per_second <= 0.0 {
+            return Err(DroneError::InvalidBandwidth(format!(
+                "the rate of {bytes_per_second} bytes per second must be positive and finite"
+            )));
+        }
+        if burst < MAX_PACKET_SIZE {
+            return Err(DroneError::InvalidBandwidth(format!(
+                "the burst of {burst} bytes is smaller than the biggest packet ({MAX_PACKET_SIZE} bytes)"
+            )));
+        }
+        Ok(Self {
+            bytes_per_second,
+            burst,
+            excess: ExcessPolicy::default(),
+        })
+    }
+
+    /// Sets what happens to the excess traffic
+    #[must_use]
+    pub fn excess(mut self, excess: ExcessPolicy) -> Self {
+        self.excess = excess;
+        self
+    }
+
+    #[must_use]
+    pub fn bytes_per_second(&self) -> f64 {
+        self.bytes_per_second
+    }
+
+    #[must_use]
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    #[must_use]
+    pub fn excess_policy(&self) -> ExcessPolicy {
+        self.excess
+    }
+}
+
+/// Token bucket enforcing the bandwidth of a link
+#[derive(Debug, Clone)]
+pub(crate) struct Shaper {
+    pub(crate) bandwidth: LinkBandwidth,
+    tokens: f64,
+    refilled_at: Instant,
+    /// `true` if the next packet of the outbound queue is waiting for tokens, so that it is
+    /// counted as shaped only once
+    pub(crate) holding: bool,
+}
+
+impl Shaper {
+    /// The bucket starts full
+    pub(crate) fn new(bandwidth: LinkBandwidth, now: Instant) -> Self {
+        Self {
+            bandwidth,
+            tokens: f64::from(bandwidth.burst),
+            refilled_at: now,
+            holding: false,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.bandwidth.bytes_per_second)
+            .min(f64::from(self.bandwidth.burst));
+        self.refilled_at = now;
+    }
+
+    /// Returns `true` if the packets over the bandwidth wait in the outbound queue, which then
+    /// takes their tokens when they are sent
+    pub(crate) fn queues_excess(&self) -> bool {
+        self.bandwidth.excess == ExcessPolicy::Queue
+    }
+
+    /// Takes the tokens for a packet of `size` bytes, returns `false` if there are not enough
+    pub(crate) fn try_take(&mut self, size: u32, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= f64::from(size) {
+            self.tokens -= f64::from(size);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Gives back the tokens of a packet which could not be sent after all
+    pub(crate) fn refund(&mut self, size: u32) {
+        self.tokens = (self.tokens + f64::from(size)).min(f64::from(self.bandwidth.burst));
+    }
+
+    /// Returns when there will be enough tokens for a packet of `size` bytes
+    pub(crate) fn ready_at(&self, size: u32) -> Instant {
+        let missing = (f64::from(size) - self.tokens).max(0.0);
+        self.refilled_at + Duration::from_secs_f64(missing / self.bandwidth.bytes_per_second)
+    }
+}
+
+impl MyDrone {
+    /// Limits the bandwidth of the link to `node_id`, with `None` it is unlimited. The limit is
+    /// forgotten when the channel to `node_id` is removed.
+    /// # Errors
+    /// Returns `DroneError::UnknownNeighbor` if `node_id` is not a neighbor
+    pub fn set_neighbor_bandwidth(
+        &mut self,
+        node_id: NodeId,
+        bandwidth: Option<LinkBandwidth>,
+    ) -> Result<(), DroneError> {
+        let now = self.clock.now();
+        let Some(neighbor) = self.packet_send.get_mut(&node_id) else {
+            return Err(DroneError::UnknownNeighbor(node_id));
+        };
+        neighbor.shaper = bandwidth.map(|bandwidth| Shaper::new(bandwidth, now));
+        log::info!("bandwidth of the link to {node_id} set to {bandwidth:?}");
+        Ok(())
+    }
+
+    /// Takes the tokens for a packet entering the outbound queue of `dest` when the excess traffic
+    /// of the link is dropped, returns `false` if the packet has to be dropped
+    pub(crate) fn admit_packet(&mut self, dest: NodeId, packet: &Packet) -> bool {
+        let now = self.clock.now();
+        let Some(shaper) = self
+            .packet_send
+            .get_mut(&dest)
+            .and_then(|neighbor| neighbor.shaper.as_mut())
+        else {
+            return true;
+        };
+        shaper.queues_excess() || shaper.try_take(packet_size(packet), now)
+    }
+}
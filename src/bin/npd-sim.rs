@@ -0,0 +1,295 @@
+//! Runs a network of drones, with stand-in clients and servers, and prints the statistics of every
+//! drone.
+//!
+//! Usage: `npd-sim [OPTIONS] [TOPOLOGY]`
+//!
+//! - `--scenario <FILE>`: runs a scenario, see the `scenario` module, with `TOPOLOGY` replacing
+//!   its own topology if given. Without a scenario every client starts a flood at the beginning.
+//! - `--duration <SECONDS>`: how long the network runs, 1 second by default without a scenario
+//! - `--seed <SEED>`: makes the drops of the drones reproducible
+//! - `--log-level <LEVEL>`: `off`, `error`, `warn` (default), `info`, `debug` or `trace`, the logs
+//!   go to stderr
+//! - `--format <FORMAT>`: `text` (default) or `json`
+//!
+//! Exits with 0 if the scenario passed, 1 if it failed and 2 if it could not run.
+
+use log::{LevelFilter, Log, Metadata, Record};
+use null_pointer_drone::network;
+use null_pointer_drone::scenario::{Action, Scenario, ScenarioReport, Step};
+use null_pointer_drone::{DroneStats, PacketCounts};
+use std::fmt::Write as _;
+use std::process::ExitCode;
+use std::time::Duration;
+use wg_2024::config::Config;
+
+const USAGE: &str = "Usage: npd-sim [--scenario <FILE>] [--duration <SECONDS>] [--seed <SEED>] \
+[--log-level <LEVEL>] [--format text|json] [TOPOLOGY]";
+const DEFAULT_DURATION: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug)]
+struct Options {
+    topology: Option<String>,
+    scenario: Option<String>,
+    duration: Option<Duration>,
+    seed: Option<u64>,
+    log_level: LevelFilter,
+    format: Format,
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        topology: None,
+        scenario: None,
+        duration: None,
+        seed: None,
+        log_level: LevelFilter::Warn,
+        format: Format::Text,
+    };
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            if options.topology.replace(arg).is_some() {
+                return Err("only one topology can be given".to_string());
+            }
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+        let invalid = || format!("invalid value for {arg}: {value}");
+        match arg.as_str() {
+            "--scenario" => options.scenario = Some(value),
+            "--duration" => {
+                let seconds: f64 = value.parse().map_err(|_| invalid())?;
+                options.duration =
+                    Some(Duration::try_from_secs_f64(seconds).map_err(|_| invalid())?);
+            }
+            "--seed" => options.seed = Some(value.parse().map_err(|_| invalid())?),
+            "--log-level" => options.log_level = value.parse().map_err(|_| invalid())?,
+            "--format" => {
+                options.format = match value.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(format!("unknown option {arg}")),
+        }
+    }
+    if options.topology.is_none() && options.scenario.is_none() {
+        return Err("a topology or a scenario is needed".to_string());
+    }
+    Ok(options)
+}
+
+/// Writes the logs to stderr
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let thread = std::thread::current();
+        eprintln!(
+            "[{} {} {}] {}",
+            record.level(),
+            thread.name().unwrap_or("?"),
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+fn scenario(options: &Options) -> Result<Scenario, String> {
+    let topology = options
+        .topology
+        .as_ref()
+        .map(network::load)
+        .transpose()
+        .map_err(|error| error.to_string())?;
+    let mut scenario = match &options.scenario {
+        Some(path) => Scenario::load(path).map_err(|error| error.to_string())?,
+        None => {
+            let config: &Config = topology.as_ref().ok_or("a topology is needed")?;
+            Scenario {
+                duration: DEFAULT_DURATION,
+                seed: None,
+                steps: config
+                    .client
+                    .iter()
+                    .map(|client| Step {
+                        at: 0.0,
+                        action: Action::Flood { from: client.id },
+                    })
+                    .collect(),
+                expectations: Vec::new(),
+                config: config.clone(),
+            }
+        }
+    };
+    if let Some(config) = topology {
+        scenario.config = config;
+    }
+    if let Some(duration) = options.duration {
+        scenario.duration = duration;
+    }
+    if options.seed.is_some() {
+        scenario.seed = options.seed;
+    }
+    Ok(scenario)
+}
+
+/// name and value of a column of packet counts
+type Column = (&'static str, fn(&DroneStats) -> &PacketCounts);
+
+const COLUMNS: [Column; 4] = [
+    ("received", |stats| &stats.received),
+    ("forwarded", |stats| &stats.forwarded),
+    ("dropped", |stats| &stats.dropped),
+    ("shortcut", |stats| &stats.shortcut),
+];
+
+fn text(report: &ScenarioReport) -> String {
+    let mut out = format!("{:>5} {:>5}", "drone", "pdr");
+    for (name, _) in COLUMNS {
+        let _ = write!(out, " {name:>9}");
+    }
+    let _ = writeln!(out, " {:>9} {:>9}", "nacked", "floods");
+    for (id, stats) in &report.stats {
+        let _ = write!(out, "{id:>5} {:>5.2}", stats.pdr);
+        for (_, counts) in COLUMNS {
+            let _ = write!(out, " {:>9}", counts(stats).total());
+        }
+        let _ = writeln!(
+            out,
+            " {:>9} {:>9}",
+            stats.nacked.total(),
+            stats.flood_requests_forwarded
+        );
+    }
+    if !report.results.is_empty() || !report.panicked.is_empty() {
+        let _ = writeln!(out, "\n{report}");
+    }
+    out
+}
+
+/// Quotes `text` as a JSON string
+fn json_string(text: &str) -> String {
+    let mut out = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json(report: &ScenarioReport) -> String {
+    let counts = |counts: &PacketCounts| {
+        format!(
+            "{{\"fragment\":{},\"ack\":{},\"nack\":{},\"flood_request\":{},\"flood_response\":{}}}",
+            counts.fragment, counts.ack, counts.nack, counts.flood_request, counts.flood_response
+        )
+    };
+    let drones: Vec<String> = report
+        .stats
+        .iter()
+        .map(|(id, stats)| {
+            let mut drone = format!("{{\"id\":{id},\"pdr\":{}", stats.pdr);
+            for (name, column) in COLUMNS {
+                let _ = write!(drone, ",\"{name}\":{}", counts(column(stats)));
+            }
+            let _ = write!(
+                drone,
+                ",\"nacked\":{},\"flood_requests_forwarded\":{}}}",
+                stats.nacked.total(),
+                stats.flood_requests_forwarded
+            );
+            drone
+        })
+        .collect();
+    let expectations: Vec<String> = report
+        .results
+        .iter()
+        .map(|result| {
+            let received = result
+                .received
+                .map_or("null".to_string(), |at| at.as_secs_f64().to_string());
+            format!(
+                "{{\"expectation\":{},\"passed\":{},\"received_after\":{received}}}",
+                json_string(&result.expectation.to_string()),
+                result.passed()
+            )
+        })
+        .collect();
+    let panicked: Vec<String> = report.panicked.iter().map(ToString::to_string).collect();
+    format!(
+        "{{\"drones\":[{}],\"expectations\":[{}],\"panicked\":[{}],\"passed\":{}}}\n",
+        drones.join(","),
+        expectations.join(","),
+        panicked.join(","),
+        report.passed()
+    )
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(options.log_level);
+    }
+
+    let report = match scenario(&options)
+        .and_then(|scenario| scenario.run().map_err(|error| error.to_string()))
+    {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::from(2);
+        }
+    };
+    match options.format {
+        Format::Text => print!("{}", text(&report)),
+        Format::Json => print!("{}", json(&report)),
+    }
+    if report.passed() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::json_string;
+
+    #[test]
+    fn json_strings_are_escaped() {
+        assert_eq!(json_string("node 20"), r#""node 20""#);
+        assert_eq!(
+            json_string("a \"b\" c\\d\ne\u{1}"),
+            r#""a \"b\" c\\d\ne\u0001""#
+        );
+    }
+}
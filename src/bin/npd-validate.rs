@@ -0,0 +1,41 @@
+//! Checks WG-2024 topology files, reporting every problem of each of them.
+//!
+//! Usage: `npd-validate <topology.toml>...`
+//!
+//! Exits with 0 if all the topologies are valid, 1 if one has problems and 2 if one cannot be read.
+
+use null_pointer_drone::{network, validate_topology};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: npd-validate <topology.toml>...");
+        return ExitCode::from(2);
+    }
+
+    let mut code = ExitCode::SUCCESS;
+    for path in paths {
+        let config = match network::load(&path) {
+            Ok(config) => config,
+            Err(error) => {
+                eprintln!("{path}: {error}");
+                code = ExitCode::from(2);
+                continue;
+            }
+        };
+        let issues = validate_topology(&config);
+        if issues.is_empty() {
+            println!("{path}: valid");
+            continue;
+        }
+        println!("{path}: {} problems", issues.len());
+        for issue in issues {
+            println!("- {issue}");
+        }
+        if code == ExitCode::SUCCESS {
+            code = ExitCode::FAILURE;
+        }
+    }
+    code
+}
@@ -0,0 +1,266 @@
+use crate::flood_cache::DEFAULT_FLOOD_CACHE_CAPACITY;
+use crate::rng::DroneRng;
+use crate::{
+    Clock, DroneError, DropModel, FaultPolicy, FloodRateLimit, LinkBandwidth, LinkDelay, MyDrone,
+    PdrSchedule, QueueConfig, Scheduler,
+};
+use crossbeam_channel::{Receiver, Sender};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Builds a `MyDrone` step by step, validating everything in `MyDroneBuilder::build` instead of
+/// panicking like `Drone::new`.
+/// ```ignore
+/// let drone = MyDroneBuilder::new(id, controller_send, controller_recv, packet_recv, packet_send)
+///     .pdr(0.05)
+///     .seed(42)
+///     .fault_policy(FaultPolicy::uniform(FaultAction::DropAndLog))
+///     .build()?;
+/// ```
+#[derive(Debug)]
+pub struct MyDroneBuilder {
+    id: NodeId,
+    controller_send: Sender<DroneEvent>,
+    controller_recv: Receiver<DroneCommand>,
+    packet_recv: Receiver<Packet>,
+    packet_send: HashMap<NodeId, Sender<Packet>>,
+    pdr: f32,
+    rng: Option<DroneRng>,
+    clock: Option<Arc<dyn Clock>>,
+    drop_model: Option<Box<dyn DropModel>>,
+    pdr_schedule: Option<PdrSchedule>,
+    neighbor_pdrs: HashMap<NodeId, f32>,
+    fault_policy: FaultPolicy,
+    flood_cache: (NonZeroUsize, Option<Duration>),
+    flood_rate_limit: Option<FloodRateLimit>,
+    queue_config: QueueConfig,
+    scheduler: Scheduler,
+    neighbor_delays: HashMap<NodeId, LinkDelay>,
+    neighbor_bandwidths: HashMap<NodeId, LinkBandwidth>,
+}
+
+impl MyDroneBuilder {
+    /// Starts building a drone with the mandatory id and channels, the drone has a pdr of 0.0
+    /// unless `MyDroneBuilder::pdr` is used
+    #[must_use]
+    pub fn new(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+    ) -> Self {
+        Self {
+            id,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            packet_send,
+            pdr: 0.0,
+            rng: None,
+            clock: None,
+            drop_model: None,
+            pdr_schedule: None,
+            neighbor_pdrs: HashMap::new(),
+            fault_policy: FaultPolicy::strict(),
+            flood_cache: (DEFAULT_FLOOD_CACHE_CAPACITY, None),
+            flood_rate_limit: None,
+            queue_config: QueueConfig::default(),
+            scheduler: Scheduler::default(),
+            neighbor_delays: HashMap::new(),
+            neighbor_bandwidths: HashMap::new(),
+        }
+    }
+
+    /// Adds a channel to a neighbor, replacing the one given for the same node
+    #[must_use]
+    pub fn neighbor(mut self, node_id: NodeId, sender: Sender<Packet>) -> Self {
+        self.packet_send.insert(node_id, sender);
+        self
+    }
+
+    #[must_use]
+    pub fn pdr(mut self, pdr: f32) -> Self {
+        self.pdr = pdr;
+        self
+    }
+
+    /// See `MyDrone::with_seed`
+    #[must_use]
+    pub fn seed(self, seed: u64) -> Self {
+        self.rng(StdRng::seed_from_u64(seed))
+    }
+
+    /// See `MyDrone::with_rng`
+    #[must_use]
+    pub fn rng(mut self, rng: impl RngCore + Send + 'static) -> Self {
+        self.rng = Some(DroneRng::new(rng));
+        self
+    }
+
+    /// See `MyDrone::with_clock`, the clock is set before everything that depends on time
+    #[must_use]
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// See `MyDrone::with_drop_model`, the drop model replaces the one given by the pdr, so it
+    /// cannot be used together with `MyDroneBuilder::pdr_schedule`
+    #[must_use]
+    pub fn drop_model(mut self, drop_model: impl DropModel + 'static) -> Self {
+        self.drop_model = Some(Box::new(drop_model));
+        self
+    }
+
+    /// See `MyDrone::with_pdr_schedule`, the schedule starts when the drone is built
+    #[must_use]
+    pub fn pdr_schedule(mut self, pdr_schedule: PdrSchedule) -> Self {
+        self.pdr_schedule = Some(pdr_schedule);
+        self
+    }
+
+    /// See `MyDrone::set_neighbor_pdr`
+    #[must_use]
+    pub fn neighbor_pdr(mut self, node_id: NodeId, pdr: f32) -> Self {
+        self.neighbor_pdrs.insert(node_id, pdr);
+        self
+    }
+
+    /// See `MyDrone::set_neighbor_delay`
+    #[must_use]
+    pub fn neighbor_delay(mut self, node_id: NodeId, delay: LinkDelay) -> Self {
+        self.neighbor_delays.insert(node_id, delay);
+        self
+    }
+
+    /// See `MyDrone::set_neighbor_bandwidth`
+    #[must_use]
+    pub fn neighbor_bandwidth(mut self, node_id: NodeId, bandwidth: LinkBandwidth) -> Self {
+        self.neighbor_bandwidths.insert(node_id, bandwidth);
+        self
+    }
+
+    /// See `MyDrone::with_fault_policy`
+    #[must_use]
+    pub fn fault_policy(mut self, fault_policy: FaultPolicy) -> Self {
+        self.fault_policy = fault_policy;
+        self
+    }
+
+    /// See `MyDrone::with_flood_cache`
+    #[must_use]
+    pub fn flood_cache(mut self, capacity: NonZeroUsize, ttl: Option<Duration>) -> Self {
+        self.flood_cache = (capacity, ttl);
+        self
+    }
+
+    /// See `MyDrone::with_flood_rate_limit`
+    #[must_use]
+    pub fn flood_rate_limit(mut self, limit: FloodRateLimit) -> Self {
+        self.flood_rate_limit = Some(limit);
+        self
+    }
+
+    /// See `MyDrone::with_outbound_queue`
+    #[must_use]
+    pub fn outbound_queue(mut self, queue_config: QueueConfig) -> Self {
+        self.queue_config = queue_config;
+        self
+    }
+
+    /// See `MyDrone::with_scheduler`
+    #[must_use]
+    pub fn scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// Builds the drone
+    /// # Errors
+    /// - `DroneError::InvalidPdr` if the pdr, the pdr of a neighbor or a pdr given by the pdr
+    ///   schedule are not in range (0.0..=1.0)
+    /// - `DroneError::SelfChannel` if there is a channel to the id of the drone
+    /// - `DroneError::UnknownNeighbor` if there is a pdr, a delay or a bandwidth for a node which
+    ///   is not a neighbor
+    /// - `DroneError::InvalidPdrSchedule` if the pdr schedule is malformed, or if there is also a
+    ///   drop model, which the schedule would replace the first time it changes the pdr
+    /// - `DroneError::InvalidDelay` if the delay model of a neighbor is invalid, see
+    ///   `DelayModel::validate`
+    pub fn build(self) -> Result<MyDrone, DroneError> {
+        if self.pdr_schedule.is_some() && self.drop_model.is_some() {
+            return Err(DroneError::InvalidPdrSchedule(
+                "a pdr schedule cannot be used with a custom drop model".to_string(),
+            ));
+        }
+        let rng = self
+            .rng
+            .unwrap_or_else(|| DroneRng::new(StdRng::from_os_rng()));
+        let mut drone = MyDrone::try_new(
+            self.id,
+            self.controller_send,
+            self.controller_recv,
+            self.packet_recv,
+            self.packet_send,
+            self.pdr,
+            rng,
+        )?;
+        if let Some(clock) = self.clock {
+            log::info!("clock set to {clock:?}");
+            drone.clock = clock;
+        }
+
+        if let Some(pdr_schedule) = self.pdr_schedule {
+            drone.set_pdr_schedule(Some(pdr_schedule))?;
+        }
+        if let Some(drop_model) = self.drop_model {
+            log::info!("drop model set to {drop_model:?}");
+            drone.drop_model = drop_model;
+            drone.custom_drop_model = true;
+        }
+        for (node_id, pdr) in self.neighbor_pdrs {
+            drone.set_neighbor_pdr(node_id, Some(pdr))?;
+        }
+        for (node_id, delay) in self.neighbor_delays {
+            drone.set_neighbor_delay(node_id, Some(delay))?;
+        }
+        for (node_id, bandwidth) in self.neighbor_bandwidths {
+            drone.set_neighbor_bandwidth(node_id, Some(bandwidth))?;
+        }
+        let (capacity, ttl) = self.flood_cache;
+        let mut drone = drone
+            .with_fault_policy(self.fault_policy)
+            .with_flood_cache(capacity, ttl)
+            .with_outbound_queue(self.queue_config)
+            .with_scheduler(self.scheduler);
+        drone.set_flood_rate_limit(self.flood_rate_limit);
+        Ok(drone)
+    }
+}
+
+impl MyDrone {
+    /// Starts building a drone, see `MyDroneBuilder`
+    #[must_use]
+    pub fn builder(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+    ) -> MyDroneBuilder {
+        MyDroneBuilder::new(
+            id,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            packet_send,
+        )
+    }
+}
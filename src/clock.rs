@@ -0,0 +1,112 @@
+use crate::MyDrone;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Source of time of a drone, used by everything that depends on time: the ttl of the flood
+/// cache, pdr schedules, link delays, bandwidth limits and flood rate limits.
+///
+/// Only the retries of the packets waiting for room in a full channel use the real time, since
+/// they wait for the neighbors and not for the clock.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current time
+    fn now(&self) -> Instant;
+
+    /// Returns a channel which receives the time once the clock reaches `deadline`, used by the
+    /// drone to wake up while it waits for packets
+    fn at(&self, deadline: Instant) -> Receiver<Instant>;
+}
+
+/// The real time, the clock used by default
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn at(&self, deadline: Instant) -> Receiver<Instant> {
+        crossbeam_channel::at(deadline)
+    }
+}
+
+/// A clock which only moves forward when `MockClock::advance` is called, so that tests can step
+/// time explicitly instead of sleeping. Its clones share the same time, so a test can keep one
+/// and give the others to the drones.
+#[derive(Clone, Debug, Default)]
+pub struct MockClock(Arc<Mutex<MockTime>>);
+
+#[derive(Debug)]
+struct MockTime {
+    now: Instant,
+    /// channels returned by `MockClock::at` which are waiting for their deadline
+    waiting: Vec<(Instant, Sender<Instant>)>,
+}
+
+impl Default for MockTime {
+    fn default() -> Self {
+        Self {
+            now: Instant::now(),
+            waiting: Vec::new(),
+        }
+    }
+}
+
+impl MockClock {
+    /// Creates a clock which starts at the current real time and stays there
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock forward by `duration`, waking up the drones waiting for a time which has
+    /// been reached
+    pub fn advance(&self, duration: Duration) {
+        let mut time = self.lock();
+        time.now += duration;
+        let now = time.now;
+        time.waiting.retain(|(deadline, sender)| {
+            if *deadline > now {
+                return true;
+            }
+            // the receiver is gone if the drone woke up for something else in the meantime
+            let _ = sender.try_send(now);
+            false
+        });
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockTime> {
+        // the time is still meaningful if a thread panicked while holding the lock
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.lock().now
+    }
+
+    fn at(&self, deadline: Instant) -> Receiver<Instant> {
+        let (sender, receiver) = bounded(1);
+        let mut time = self.lock();
+        if deadline <= time.now {
+            let _ = sender.try_send(time.now);
+        } else {
+            time.waiting.push((deadline, sender));
+        }
+        receiver
+    }
+}
+
+impl MyDrone {
+    /// Sets the clock of the drone. It should be set right after creating the drone, since the
+    /// times already taken from the previous clock, like the start of a pdr schedule, are kept.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        log::info!("clock set to {clock:?}");
+        self.clock = Arc::new(clock);
+        self
+    }
+}
@@ -1,58 +1,212 @@
-use crate::{MyDrone, State};
+use crate::flood_cache::FloodCache;
+use crate::neighbor::Neighbor;
+use crate::{
+    Bernoulli, ControlCommand, DroneError, DropModel, FaultPolicy, LinkStats, MyDrone, State,
+};
 use crossbeam_channel::Sender;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+use wg_2024::controller::DroneCommand;
 use wg_2024::network::NodeId;
 use wg_2024::packet::Packet;
 
 impl MyDrone {
-    /// Sets `self.pdr` to the given `pdr` value.
-    /// # Panics
-    /// Panics if `pdr` is not in the valid range
-    pub(crate) fn set_pdr(&mut self, pdr: f32) {
-        assert!(
-            (0f32..=1f32).contains(&pdr),
-            "Tried to set an invalid pdr value of {pdr}, which is not in range (0.0..=1.0)"
-        );
-        self.pdr = pdr;
+    /// Applies a command received from the simulation controller
+    /// # Errors
+    /// Returns the error of the setter corresponding to the command
+    pub(crate) fn process_command(&mut self, command: DroneCommand) -> Result<(), DroneError> {
+        match command {
+            DroneCommand::AddSender(node_id, sender) => self.add_channel(node_id, sender),
+            DroneCommand::SetPacketDropRate(pdr) => {
+                self.set_pdr(pdr)?;
+                if self.pdr_schedule.take().is_some() {
+                    log::info!("pdr schedule removed by the simulation controller");
+                }
+                Ok(())
+            }
+            DroneCommand::Crash => {
+                self.set_state(State::Crashing);
+                Ok(())
+            }
+            DroneCommand::RemoveSender(node_id_to_be_removed) => {
+                self.remove_channel(node_id_to_be_removed)
+            }
+        }
+    }
+
+    /// Applies a command received through the control channel
+    /// # Errors
+    /// Returns the error of the setter corresponding to the command
+    pub(crate) fn process_control_command(
+        &mut self,
+        command: ControlCommand,
+    ) -> Result<(), DroneError> {
+        match command {
+            ControlCommand::SetNeighborPdr(node_id, pdr) => self.set_neighbor_pdr(node_id, pdr),
+            ControlCommand::SetPdrSchedule(pdr_schedule) => self.set_pdr_schedule(pdr_schedule),
+            ControlCommand::SetNeighborDelay(node_id, delay) => {
+                self.set_neighbor_delay(node_id, delay)
+            }
+            ControlCommand::SetNeighborBandwidth(node_id, bandwidth) => {
+                self.set_neighbor_bandwidth(node_id, bandwidth)
+            }
+            ControlCommand::SetFloodRateLimit(limit) => {
+                self.set_flood_rate_limit(limit);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns a sender for the control channel of the drone, which can be used to send
+    /// `ControlCommand`s while the drone is running
+    #[must_use]
+    pub fn control_sender(&self) -> Sender<ControlCommand> {
+        self.control_send.clone()
+    }
+
+    /// Sets `self.pdr` to the given `pdr` value, replacing the current drop model with a
+    /// `Bernoulli` one with that pdr.
+    /// # Errors
+    /// Returns `DroneError::InvalidPdr` if `pdr` is not in the valid range
+    pub(crate) fn set_pdr(&mut self, pdr: f32) -> Result<(), DroneError> {
+        self.replace_pdr(pdr)?;
         log::info!("pdr set to {pdr}");
+        Ok(())
+    }
+
+    /// `set_pdr` without logging, for the pdr schedule which may change the pdr at every fragment
+    /// # Errors
+    /// Returns `DroneError::InvalidPdr` if `pdr` is not in the valid range
+    pub(crate) fn replace_pdr(&mut self, pdr: f32) -> Result<(), DroneError> {
+        let drop_model = Bernoulli::new(pdr)?;
+        self.pdr = pdr;
+        self.drop_model = Box::new(drop_model);
+        self.custom_drop_model = false;
+        self.record(|stats| stats.pdr = pdr);
+        Ok(())
+    }
+
+    /// Replaces the drop model of the drone, which by default drops fragments with probability
+    /// `pdr`. The pdr schedule of the drone, if any, is removed since it would replace the model.
+    /// Note that a `DroneCommand::SetPacketDropRate` installs a `Bernoulli` model again.
+    #[must_use]
+    pub fn with_drop_model(mut self, drop_model: impl DropModel + 'static) -> Self {
+        log::info!("drop model set to {drop_model:?}");
+        if self.pdr_schedule.take().is_some() {
+            log::warn!("pdr schedule removed by the drop model");
+        }
+        self.drop_model = Box::new(drop_model);
+        self.custom_drop_model = true;
+        self
+    }
+
+    /// Sets the pdr of the link to `node_id` only, with `None` the link falls back to the drop
+    /// model of the drone. The pdr is forgotten when the channel to `node_id` is removed.
+    /// # Errors
+    /// - `DroneError::UnknownNeighbor` if `node_id` is not a neighbor
+    /// - `DroneError::InvalidPdr` if `pdr` is not in the valid range
+    pub fn set_neighbor_pdr(
+        &mut self,
+        node_id: NodeId,
+        pdr: Option<f32>,
+    ) -> Result<(), DroneError> {
+        if let Some(pdr) = pdr {
+            Bernoulli::new(pdr)?;
+        }
+        let Some(neighbor) = self.packet_send.get_mut(&node_id) else {
+            return Err(DroneError::UnknownNeighbor(node_id));
+        };
+        neighbor.pdr = pdr;
+        log::info!("pdr of the link to {node_id} set to {pdr:?}");
+        Ok(())
     }
 
     /// Adds a channel to send `Packet`s on
-    /// # Panics
-    /// Panics if the new channel id is the same as the drone's
-    pub(crate) fn add_channel(&mut self, id: NodeId, sender: Sender<Packet>) {
-        assert_ne!(
-            id, self.id,
-            "Cannot add a channel with the same NodeId of this drone (which is {})",
-            self.id
-        );
-
-        match self.packet_send.insert(id, sender) {
-            Some(_previous_sender) => {
+    /// # Errors
+    /// Returns `DroneError::SelfChannel` if the new channel id is the same as the drone's
+    pub(crate) fn add_channel(
+        &mut self,
+        id: NodeId,
+        sender: Sender<Packet>,
+    ) -> Result<(), DroneError> {
+        if id == self.id {
+            return Err(DroneError::SelfChannel(id));
+        }
+
+        match self.packet_send.get_mut(&id) {
+            Some(neighbor) => {
+                neighbor.sender = sender;
                 log::info!("Sender channel to node {id} updated");
             }
             None => {
+                self.packet_send.insert(id, Neighbor::new(sender));
+                self.record(|stats| {
+                    stats.links.insert(id, LinkStats::default());
+                });
                 log::info!("Sender channel to node {id} inserted");
             }
         }
+        Ok(())
     }
 
     /// Removes a channel to send `Packet`s on
-    /// # Panics
-    /// Panics if there is no channel associated to `node_id`
-    pub(crate) fn remove_channel(&mut self, node_id: NodeId) {
+    /// # Errors
+    /// Returns `DroneError::UnknownChannel` if there is no channel associated to `node_id`
+    pub(crate) fn remove_channel(&mut self, node_id: NodeId) -> Result<(), DroneError> {
         match self.packet_send.remove(&node_id) {
-            Some(_removed_channel) => {
+            Some(removed) => {
+                let lost = removed.queue.len() + removed.delayed.len();
+                if lost > 0 {
+                    log::warn!("{lost} packets waiting for {node_id} are lost with the channel");
+                }
+                self.record(|stats| {
+                    stats.links.remove(&node_id);
+                });
                 log::info!("Channel to {node_id} removed successfully");
+                Ok(())
             }
-            None => {
-                panic!("Cannot remove channel to {node_id}: it does not exist")
-            }
+            None => Err(DroneError::UnknownChannel(node_id)),
         }
     }
 
+    /// Sets the policy used to react to errors, replacing the default strict one which panics on
+    /// every error
+    #[must_use]
+    pub fn with_fault_policy(mut self, fault_policy: FaultPolicy) -> Self {
+        log::info!("fault policy set to {fault_policy:?}");
+        self.fault_policy = fault_policy;
+        self
+    }
+
+    /// Replaces the cache of the flood requests already seen by the drone with an empty one which
+    /// remembers at most `capacity` floods, forgetting the least recently seen ones first. With a
+    /// `ttl` floods are also forgotten when they have not been seen for longer than `ttl`.
+    /// A forgotten flood is forwarded again if the drone receives it.
+    #[must_use]
+    pub fn with_flood_cache(mut self, capacity: NonZeroUsize, ttl: Option<Duration>) -> Self {
+        log::info!("flood cache set to capacity {capacity} and ttl {ttl:?}");
+        self.known_flood_ids = FloodCache::new(capacity, ttl);
+        self
+    }
+
+    /// Returns how many floods are currently remembered by the flood cache, same as the
+    /// `flood_cache_len` of `MyDrone::stats`
+    #[must_use]
+    pub fn flood_cache_len(&self) -> usize {
+        self.stats().flood_cache_len
+    }
+
+    /// Returns how many floods have been evicted from the flood cache, same as the
+    /// `flood_cache_evictions` of `MyDrone::stats`
+    #[must_use]
+    pub fn flood_cache_evictions(&self) -> u64 {
+        self.stats().flood_cache_evictions
+    }
+
     /// Updates the state of the drone
     pub(crate) fn set_state(&mut self, state: State) {
         self.state = state;
+        self.record(|stats| stats.state = state);
         log::info!("state set to {state:?}");
     }
 }
@@ -0,0 +1,23 @@
+use crate::{FloodRateLimit, LinkBandwidth, LinkDelay, PdrSchedule};
+use wg_2024::network::NodeId;
+
+/// Commands for the features of this drone which are not covered by `DroneCommand`.
+///
+/// They are sent through the channel returned by `MyDrone::control_sender` and are processed
+/// right after the commands of the simulation controller.
+#[derive(Debug, Clone)]
+pub enum ControlCommand {
+    /// Sets the pdr of the link to the given neighbor, `None` makes it fall back to the drop
+    /// model of the drone
+    SetNeighborPdr(NodeId, Option<f32>),
+    /// Installs a pdr schedule starting now, `None` removes the current one and keeps the last pdr
+    /// it gave
+    SetPdrSchedule(Option<PdrSchedule>),
+    /// Limits how many flood requests of the same initiator are forwarded, `None` removes the
+    /// limit
+    SetFloodRateLimit(Option<FloodRateLimit>),
+    /// Delays the packets sent to the given neighbor, `None` sends them right away
+    SetNeighborDelay(NodeId, Option<LinkDelay>),
+    /// Limits the bandwidth of the link to the given neighbor, `None` makes it unlimited
+    SetNeighborBandwidth(NodeId, Option<LinkBandwidth>),
+}
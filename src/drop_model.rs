@@ -0,0 +1,164 @@
+use crate::DroneError;
+use rand::{Rng, RngCore};
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use wg_2024::packet::Fragment;
+
+/// Decides which fragments a drone drops instead of forwarding them.
+///
+/// Only `MsgFragment`s can be dropped, all the other packet types are always forwarded.
+pub trait DropModel: Debug + Send {
+    /// Called for every fragment the drone is about to send to a neighbor, returns `true` if the
+    /// fragment has to be dropped. `rng` is the random number generator owned by the drone.
+    fn should_drop(&mut self, fragment: &Fragment, rng: &mut dyn RngCore) -> bool;
+}
+
+/// Returns `DroneError::InvalidPdr` if `probability` is not in range (0.0..=1.0)
+pub(crate) fn check_probability(probability: f32) -> Result<f32, DroneError> {
+    if (0f32..=1f32).contains(&probability) {
+        Ok(probability)
+    } else {
+        Err(DroneError::InvalidPdr(probability))
+    }
+}
+
+/// Rolls a dice and returns `true` with the given `probability`
+pub(crate) fn roll(probability: f32, rng: &mut dyn RngCore) -> bool {
+    let random_number: f32 = rng.random_range(0.0..=1.0);
+    random_number < probability
+}
+
+/// Drops every fragment independently with probability `pdr`, this is the default model used by
+/// the drone and the one reconfigured by `DroneCommand::SetPacketDropRate`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Bernoulli {
+    pdr: f32,
+}
+
+impl Bernoulli {
+    /// # Errors
+    /// Returns `DroneError::InvalidPdr` if `pdr` is not in range (0.0..=1.0)
+    pub fn new(pdr: f32) -> Result<Self, DroneError> {
+        Ok(Self {
+            pdr: check_probability(pdr)?,
+        })
+    }
+
+    #[must_use]
+    pub fn pdr(&self) -> f32 {
+        self.pdr
+    }
+}
+
+impl DropModel for Bernoulli {
+    fn should_drop(&mut self, _fragment: &Fragment, rng: &mut dyn RngCore) -> bool {
+        roll(self.pdr, rng)
+    }
+}
+
+/// Bursty losses: the link alternates between a good and a bad state, each with its own loss
+/// probability. Before every fragment the link moves from good to bad with probability
+/// `p_good_to_bad` and from bad to good with probability `p_bad_to_good`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GilbertElliott {
+    p_good_to_bad: f32,
+    p_bad_to_good: f32,
+    loss_good: f32,
+    loss_bad: f32,
+    bad: bool,
+}
+
+impl GilbertElliott {
+    /// Creates the model starting in the good state
+    /// # Errors
+    /// Returns `DroneError::InvalidPdr` if any of the probabilities is not in range (0.0..=1.0)
+    pub fn new(
+        p_good_to_bad: f32,
+        p_bad_to_good: f32,
+        loss_good: f32,
+        loss_bad: f32,
+    ) -> Result<Self, DroneError> {
+        Ok(Self {
+            p_good_to_bad: check_probability(p_good_to_bad)?,
+            p_bad_to_good: check_probability(p_bad_to_good)?,
+            loss_good: check_probability(loss_good)?,
+            loss_bad: check_probability(loss_bad)?,
+            bad: false,
+        })
+    }
+
+    /// Returns `true` if the link is currently in the bad state
+    #[must_use]
+    pub fn is_bad(&self) -> bool {
+        self.bad
+    }
+}
+
+impl DropModel for GilbertElliott {
+    fn should_drop(&mut self, _fragment: &Fragment, rng: &mut dyn RngCore) -> bool {
+        let switch_probability = if self.bad {
+            self.p_bad_to_good
+        } else {
+            self.p_good_to_bad
+        };
+        if roll(switch_probability, rng) {
+            self.bad = !self.bad;
+        }
+
+        let loss = if self.bad {
+            self.loss_bad
+        } else {
+            self.loss_good
+        };
+        roll(loss, rng)
+    }
+}
+
+/// Deterministically drops the n-th, 2n-th, 3n-th... fragment handled by the drone, with `n` 0 it
+/// never drops anything
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EveryNth {
+    n: u64,
+    seen: u64,
+}
+
+impl EveryNth {
+    #[must_use]
+    pub fn new(n: u64) -> Self {
+        Self { n, seen: 0 }
+    }
+}
+
+impl DropModel for EveryNth {
+    fn should_drop(&mut self, _fragment: &Fragment, _rng: &mut dyn RngCore) -> bool {
+        self.seen += 1;
+        self.n != 0 && self.seen.is_multiple_of(self.n)
+    }
+}
+
+/// Drops exactly the fragments at the given positions in the sequence of fragments handled by
+/// the drone, counting from 0. This is not the `fragment_index` inside the fragment, so that
+/// retransmissions of a dropped fragment are not necessarily dropped again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scripted {
+    to_drop: BTreeSet<u64>,
+    seen: u64,
+}
+
+impl Scripted {
+    #[must_use]
+    pub fn new(to_drop: impl IntoIterator<Item = u64>) -> Self {
+        Self {
+            to_drop: to_drop.into_iter().collect(),
+            seen: 0,
+        }
+    }
+}
+
+impl DropModel for Scripted {
+    fn should_drop(&mut self, _fragment: &Fragment, _rng: &mut dyn RngCore) -> bool {
+        let position = self.seen;
+        self.seen += 1;
+        self.to_drop.contains(&position)
+    }
+}
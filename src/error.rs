@@ -0,0 +1,140 @@
+use crate::ErrorClass;
+use crossbeam_channel::{SendError, Sender};
+use std::fmt::{Display, Formatter};
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Every anomaly the drone can run into while configuring itself, processing packets or talking
+/// to the simulation controller.
+///
+/// The `Display` implementation of each variant is the message the drone panics with when its
+/// `FaultPolicy` treats that error as fatal. Packets are boxed to keep the `Result`s returned by the
+/// drone small.
+#[derive(Debug, Clone)]
+pub enum DroneError {
+    /// The `Sender<DroneCommand>` end of the simulation controller channel got dropped
+    ControllerDisconnected,
+    /// All the senders to the drone's packet receiver got dropped before a `DroneCommand::Crash`
+    PacketChannelDisconnected,
+    /// Tried to set a pdr outside of the range `0.0..=1.0`
+    InvalidPdr(f32),
+    /// Tried to add a channel to a node with the same `NodeId` of this drone
+    SelfChannel(NodeId),
+    /// Tried to remove a channel to a node which is not a neighbor
+    UnknownChannel(NodeId),
+    /// Tried to configure the link to a node which is not a neighbor
+    UnknownNeighbor(NodeId),
+    /// A pdr schedule is malformed or cannot be read
+    InvalidPdrSchedule(String),
+    /// Tried to set a flood rate limit which is not a positive finite number of requests per
+    /// second
+    InvalidFloodRateLimit(f64),
+    /// A link delay model has invalid parameters
+    InvalidDelay(String),
+    /// A link bandwidth has invalid parameters
+    InvalidBandwidth(String),
+    /// Received a packet whose routing header has no hops
+    EmptyRoutingHeader(Box<Packet>),
+    /// Received a packet whose `hop_index` is not a valid index of its hops
+    HopIndexOutOfBounds(Box<Packet>),
+    /// Received a packet which is not a flood request with `hop_index` 0
+    HopIndexZero(Box<Packet>),
+    /// Received a flood request with an empty path trace
+    MissingPathTrace { flood_id: u64, initiator_id: NodeId },
+    /// The crossbeam channel to a neighbor returned an error when sending a packet
+    PacketSendFailed {
+        channel: Sender<Packet>,
+        error: Box<SendError<Packet>>,
+    },
+    /// The crossbeam channel to the simulation controller returned an error when sending an event
+    EventSendFailed(Box<SendError<DroneEvent>>),
+}
+
+impl Display for DroneError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DroneError::ControllerDisconnected => write!(
+                f,
+                "The Sender<DroneCommand> end of the simulation controller channel unexpectedly got dropped"
+            ),
+            DroneError::PacketChannelDisconnected => write!(
+                f,
+                "There is no connected sender to the drone's packet receiver channel and no DroneCommand::Crash has been received"
+            ),
+            DroneError::InvalidPdr(pdr) => write!(
+                f,
+                "Tried to set an invalid pdr value of {pdr}, which is not in range (0.0..=1.0)"
+            ),
+            DroneError::SelfChannel(id) => write!(
+                f,
+                "Cannot add a channel with the same NodeId of this drone (which is {id})"
+            ),
+            DroneError::UnknownChannel(node_id) => {
+                write!(f, "Cannot remove channel to {node_id}: it does not exist")
+            }
+            DroneError::UnknownNeighbor(node_id) => {
+                write!(f, "Node {node_id} is not a neighbor of this drone")
+            }
+            DroneError::InvalidPdrSchedule(reason) => {
+                write!(f, "Invalid pdr schedule: {reason}")
+            }
+            DroneError::InvalidFloodRateLimit(rate) => write!(
+                f,
+                "Invalid flood rate limit of {rate} requests per second, it must be positive and finite"
+            ),
+            DroneError::InvalidDelay(reason) => write!(f, "Invalid delay: {reason}"),
+            DroneError::InvalidBandwidth(reason) => write!(f, "Invalid bandwidth: {reason}"),
+            DroneError::EmptyRoutingHeader(packet) => {
+                write!(f, "empty routing header for packet {packet}")
+            }
+            DroneError::HopIndexOutOfBounds(packet) => write!(
+                f,
+                "hop_index out of bounds: index {} for hops {:?}",
+                packet.routing_header.hop_index, packet.routing_header.hops
+            ),
+            DroneError::HopIndexZero(_packet) => write!(
+                f,
+                "received packet with hop_index 0, which should be impossible"
+            ),
+            DroneError::MissingPathTrace { .. } => write!(f, "flood request has no path trace"),
+            DroneError::PacketSendFailed { channel, error } => write!(
+                f,
+                "Cannot send packet {} into channel {channel:?}. Error: {error:?}",
+                error.0
+            ),
+            DroneError::EventSendFailed(error) => write!(
+                f,
+                "Cannot send event {:?} to simulation controller. Error: {error:?}",
+                error.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DroneError {}
+
+impl DroneError {
+    /// Returns the class used by the `FaultPolicy` to decide how to react to this error
+    #[must_use]
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            DroneError::EmptyRoutingHeader(_)
+            | DroneError::HopIndexOutOfBounds(_)
+            | DroneError::HopIndexZero(_) => ErrorClass::MalformedRoutingHeader,
+            DroneError::MissingPathTrace { .. } => ErrorClass::MalformedFloodRequest,
+            DroneError::InvalidPdr(_)
+            | DroneError::SelfChannel(_)
+            | DroneError::UnknownChannel(_)
+            | DroneError::UnknownNeighbor(_)
+            | DroneError::InvalidPdrSchedule(_)
+            | DroneError::InvalidFloodRateLimit(_)
+            | DroneError::InvalidDelay(_)
+            | DroneError::InvalidBandwidth(_) => ErrorClass::InvalidCommand,
+            DroneError::ControllerDisconnected
+            | DroneError::PacketChannelDisconnected
+            | DroneError::PacketSendFailed { .. }
+            | DroneError::EventSendFailed(_) => ErrorClass::ChannelFailure,
+        }
+    }
+}
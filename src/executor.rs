@@ -0,0 +1,133 @@
+use crate::{ControlCommand, MyDrone, StepOutcome};
+use crossbeam_channel::{never, Receiver, Select};
+use std::collections::HashMap;
+use std::io;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use wg_2024::controller::DroneCommand;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Most steps a worker makes on the same drone before waiting on all of its drones again, so that
+/// a drone flooded with packets cannot starve the others of the same worker
+const MAX_STEPS_IN_A_ROW: usize = 64;
+
+/// How a drone run by a `DroneExecutor` stopped: `Ok` once it has finished crashing, or the payload
+/// of the panic which stopped it, as returned by `JoinHandle::join` for a drone with its own thread
+pub type DroneExit = thread::Result<()>;
+
+/// Runs many drones on a fixed pool of worker threads, instead of one thread per drone.
+///
+/// The drones are spread round robin over the workers. Each worker waits on the channels and the
+/// timers of all its drones at once with a crossbeam `Select`, and lets the drone that has
+/// something ready do it with `MyDrone::step`, so a drone still processes the commands of the
+/// simulation controller before anything else. A drone leaves its worker when `step` returns
+/// `StepOutcome::Finished`, i.e. when it is crashing and all the senders to its packet channel are
+/// dropped, exactly when `run` would return, or when it panics, which does not affect the other
+/// drones of the worker.
+#[derive(Debug)]
+pub struct DroneExecutor {
+    workers: Vec<JoinHandle<Vec<(NodeId, DroneExit)>>>,
+}
+
+impl DroneExecutor {
+    /// Starts `workers` threads running `drones`
+    /// # Errors
+    /// Returns the error of the OS if a worker thread cannot be spawned
+    pub fn spawn(
+        drones: impl IntoIterator<Item = MyDrone>,
+        workers: NonZeroUsize,
+    ) -> io::Result<Self> {
+        let mut shares: Vec<Vec<MyDrone>> = (0..workers.get()).map(|_| Vec::new()).collect();
+        for (i, drone) in drones.into_iter().enumerate() {
+            shares[i % workers.get()].push(drone);
+        }
+        let workers = shares
+            .into_iter()
+            .enumerate()
+            .map(|(i, drones)| {
+                thread::Builder::new()
+                    .name(format!("drone-worker-{i}"))
+                    .spawn(move || run_worker(drones))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { workers })
+    }
+
+    /// Waits until all the drones have stopped, returning how each of them did
+    pub fn join(self) -> HashMap<NodeId, DroneExit> {
+        let mut exits = HashMap::new();
+        for worker in self.workers {
+            // the worker catches the panics of its drones, so it cannot panic itself
+            if let Ok(worker_exits) = worker.join() {
+                exits.extend(worker_exits);
+            }
+        }
+        exits
+    }
+}
+
+/// The receivers a worker waits on for one of its drones
+struct DroneReceivers {
+    controller: Receiver<DroneCommand>,
+    control: Receiver<ControlCommand>,
+    timer: Receiver<Instant>,
+    packet: Receiver<Packet>,
+}
+
+impl MyDrone {
+    /// Returns what `run` would wait on in its `select_biased!`
+    fn receivers(&mut self) -> DroneReceivers {
+        DroneReceivers {
+            controller: self.controller_recv.clone(),
+            control: self.control_recv.clone(),
+            timer: self.timer(),
+            packet: if self.accepts_packets() {
+                self.packet_recv.clone()
+            } else {
+                never()
+            },
+        }
+    }
+}
+
+fn run_worker(mut drones: Vec<MyDrone>) -> Vec<(NodeId, DroneExit)> {
+    let mut exits = Vec::new();
+    while !drones.is_empty() {
+        let receivers: Vec<DroneReceivers> = drones.iter_mut().map(MyDrone::receivers).collect();
+        let mut select = Select::new();
+        for receivers in &receivers {
+            select.recv(&receivers.controller);
+            select.recv(&receivers.control);
+            select.recv(&receivers.timer);
+            select.recv(&receivers.packet);
+        }
+        // every drone registers 4 operations, in the order above
+        let ready = select.ready() / 4;
+        drop(select);
+        drop(receivers);
+
+        if let Some(exit) = step_ready_drone(&mut drones[ready]) {
+            let drone = drones.swap_remove(ready);
+            log::info!("drone {} left its worker", drone.id);
+            exits.push((drone.id, exit));
+        }
+    }
+    exits
+}
+
+/// Steps a drone with something ready until it is idle, returning how it stopped if it did
+fn step_ready_drone(drone: &mut MyDrone) -> Option<DroneExit> {
+    for _ in 0..MAX_STEPS_IN_A_ROW {
+        match panic::catch_unwind(AssertUnwindSafe(|| drone.step())) {
+            Ok(StepOutcome::Finished) => return Some(Ok(())),
+            Ok(StepOutcome::Command(_) | StepOutcome::Control(_) | StepOutcome::Packet(_)) => {}
+            // a timer can leave congested queues behind, retrying them right away is pointless
+            Ok(StepOutcome::Timer | StepOutcome::Disconnected | StepOutcome::Idle) => break,
+            Err(payload) => return Some(Err(payload)),
+        }
+    }
+    None
+}
@@ -0,0 +1,141 @@
+use crate::{DroneError, MyDrone, State};
+use std::collections::HashMap;
+use wg_2024::packet::{NackType, PacketType};
+
+/// How the drone reacts to a `DroneError`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FaultAction {
+    /// Panics with the message of the error, this is the strict behavior expected by the protocol
+    #[default]
+    Panic,
+    /// Logs the error with `log::error!` and keeps going, ignoring the offending packet or command
+    DropAndLog,
+    /// Answers the offending packet with the closest `NackType` if there is a route back to its
+    /// sender, otherwise behaves like `FaultAction::DropAndLog`
+    Nack,
+}
+
+/// Groups of `DroneError`s that share the same `FaultAction`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// Empty routing header, `hop_index` out of bounds or `hop_index` 0
+    MalformedRoutingHeader,
+    /// Flood request without a path trace
+    MalformedFloodRequest,
+    /// Invalid pdr, channel to the drone itself or configuration of an unknown channel
+    InvalidCommand,
+    /// A crossbeam channel of the drone got disconnected
+    ChannelFailure,
+}
+
+/// Chooses a `FaultAction` for every `ErrorClass`, the default policy panics on everything
+#[derive(Clone, Debug, Default)]
+pub struct FaultPolicy {
+    default: FaultAction,
+    overrides: HashMap<ErrorClass, FaultAction>,
+}
+
+impl FaultPolicy {
+    /// Panics on every error, same as `FaultPolicy::default()`
+    #[must_use]
+    pub fn strict() -> Self {
+        Self::default()
+    }
+
+    /// Uses `action` for every error class
+    #[must_use]
+    pub fn uniform(action: FaultAction) -> Self {
+        Self {
+            default: action,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Uses `action` for the errors of the given `class`, leaving the other classes untouched
+    #[must_use]
+    pub fn with(mut self, class: ErrorClass, action: FaultAction) -> Self {
+        self.overrides.insert(class, action);
+        self
+    }
+
+    /// Returns the action to take for errors of the given `class`
+    #[must_use]
+    pub fn action_for(&self, class: ErrorClass) -> FaultAction {
+        self.overrides.get(&class).copied().unwrap_or(self.default)
+    }
+}
+
+impl MyDrone {
+    /// Reacts to an error returned by one of the drone's internal methods according to the fault
+    /// policy of the drone
+    /// # Panics
+    /// Panics with the `Display` message of `error` if the policy says so
+    pub(crate) fn handle_error(&mut self, error: DroneError) {
+        match self.fault_policy.action_for(error.class()) {
+            FaultAction::Panic => {
+                log::error!("{error}");
+                panic!("{error}");
+            }
+            FaultAction::DropAndLog => self.drop_and_log(error),
+            FaultAction::Nack => self.nack_or_drop(error),
+        }
+    }
+
+    fn drop_and_log(&mut self, error: DroneError) {
+        log::error!("{error}, ignoring it and going on");
+        match error {
+            DroneError::ControllerDisconnected => {
+                // otherwise select_biased! would keep selecting the disconnected channel
+                log::warn!("No more commands will be received from the simulation controller");
+                self.controller_recv = crossbeam_channel::never();
+            }
+            DroneError::PacketChannelDisconnected => {
+                log::warn!("No more packets can be received, the drone is stopping");
+                self.set_state(State::Crashing);
+            }
+            _ => {}
+        }
+    }
+
+    /// Sends back a nack for the packet inside `error`, if there is one and the drone is in its
+    /// routing header
+    fn nack_or_drop(&mut self, error: DroneError) {
+        let (packet, recipient_idx, nack_type) = match &error {
+            DroneError::HopIndexOutOfBounds(packet) | DroneError::HopIndexZero(packet) => {
+                // the drone has received the packet, so the route back starts from its position
+                let position = packet
+                    .routing_header
+                    .hops
+                    .iter()
+                    .position(|node_id| *node_id == self.id);
+                (
+                    packet.as_ref(),
+                    position,
+                    NackType::UnexpectedRecipient(self.id),
+                )
+            }
+            DroneError::PacketSendFailed { error: send, .. }
+                if matches!(send.0.pack_type, PacketType::MsgFragment(_)) =>
+            {
+                // the hop_index of a packet that is being sent already points to the destination
+                let header = &send.0.routing_header;
+                (
+                    &send.0,
+                    header.hop_index.checked_sub(1),
+                    NackType::ErrorInRouting(header.current_hop().unwrap_or(self.id)),
+                )
+            }
+            _ => return self.drop_and_log(error),
+        };
+
+        match recipient_idx {
+            Some(idx) if idx > 0 => {
+                log::error!("{error}, answering with {nack_type:?}");
+                if let Err(nack_error) = self.make_and_send_nack(packet, idx, nack_type) {
+                    log::error!("Could not send {nack_type:?}: {nack_error}");
+                }
+            }
+            _ => self.drop_and_log(error),
+        }
+    }
+}
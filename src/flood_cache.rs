@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+
+/// Default number of `(flood_id, initiator_id)` pairs remembered by a drone
+pub const DEFAULT_FLOOD_CACHE_CAPACITY: NonZeroUsize = match NonZeroUsize::new(4096) {
+    Some(capacity) => capacity,
+    None => unreachable!(),
+};
+
+type FloodKey = (u64, NodeId);
+
+/// Remembers the flood requests already seen by the drone.
+///
+/// When the cache is full the least recently seen flood is evicted, and if a `ttl` is set floods
+/// which have not been seen for longer than `ttl` are evicted too. An evicted flood is treated as
+/// a new one if it is seen again.
+#[derive(Debug)]
+pub(crate) struct FloodCache {
+    capacity: NonZeroUsize,
+    ttl: Option<Duration>,
+    /// for every flood, the sequence number of the last time it was seen
+    last_seen: HashMap<FloodKey, u64>,
+    /// the floods ordered from the least to the most recently seen
+    by_recency: BTreeMap<u64, (FloodKey, Instant)>,
+    next_seq: u64,
+    evictions: u64,
+}
+
+impl FloodCache {
+    pub(crate) fn new(capacity: NonZeroUsize, ttl: Option<Duration>) -> Self {
+        Self {
+            capacity,
+            ttl,
+            last_seen: HashMap::new(),
+            by_recency: BTreeMap::new(),
+            next_seq: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Marks the flood as seen at `now`, returns `true` if it was already in the cache
+    pub(crate) fn check_and_insert(
+        &mut self,
+        flood_id: u64,
+        initiator_id: NodeId,
+        now: Instant,
+    ) -> bool {
+        self.evict_expired(now);
+
+        let key = (flood_id, initiator_id);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        let already_seen = match self.last_seen.insert(key, seq) {
+            Some(previous_seq) => {
+                self.by_recency.remove(&previous_seq);
+                true
+            }
+            None => false,
+        };
+        self.by_recency.insert(seq, (key, now));
+
+        while self.last_seen.len() > self.capacity.get() {
+            self.evict_oldest();
+        }
+        already_seen
+    }
+
+    fn evict_expired(&mut self, now: Instant) {
+        let Some(ttl) = self.ttl else {
+            return;
+        };
+        while let Some((_seq, (_key, seen_at))) = self.by_recency.first_key_value() {
+            if now.saturating_duration_since(*seen_at) < ttl {
+                break;
+            }
+            self.evict_oldest();
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some((_seq, (key, _seen_at))) = self.by_recency.pop_first() {
+            self.last_seen.remove(&key);
+            self.evictions += 1;
+            log::debug!(
+                "evicted (flood_id:{},initiator_id:{}) from the flood cache",
+                key.0,
+                key.1
+            );
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.last_seen.len()
+    }
+
+    pub(crate) fn evictions(&self) -> u64 {
+        self.evictions
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{DroneError, MyDrone};
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::time::Instant;
+use wg_2024::network::NodeId;
+
+/// How many flood requests of the same initiator a drone forwards, as a token bucket: every
+/// forwarded flood request takes a token, and tokens come back at `requests_per_second` up to
+/// `burst` of them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FloodRateLimit {
+    requests_per_second: f64,
+    burst: NonZeroU32,
+}
+
+impl FloodRateLimit {
+    /// # Errors
+    /// Returns `DroneError::InvalidFloodRateLimit` if `requests_per_second` is not a positive
+    /// finite number
+    pub fn new(requests_per_second: f64, burst: NonZeroU32) -> Result<Self, DroneError> {
+        if !requests_per_second.is_finite() || requests_per_second <= 0.0 {
+            return Err(DroneError::InvalidFloodRateLimit(requests_per_second));
+        }
+        Ok(Self {
+            requests_per_second,
+            burst,
+        })
+    }
+
+    #[must_use]
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+
+    #[must_use]
+    pub fn burst(&self) -> NonZeroU32 {
+        self.burst
+    }
+}
+
+/// Token buckets of the initiators of the flood requests seen by the drone
+#[derive(Debug, Default)]
+pub(crate) struct FloodLimiter {
+    limit: Option<FloodRateLimit>,
+    /// tokens left for every initiator and when they were last refilled
+    buckets: HashMap<NodeId, (f64, Instant)>,
+}
+
+impl FloodLimiter {
+    /// Replaces the limit, all the buckets start full again
+    pub(crate) fn set_limit(&mut self, limit: Option<FloodRateLimit>) {
+        self.limit = limit;
+        self.buckets.clear();
+    }
+
+    /// Takes a token from the bucket of `initiator_id`, returns `false` if there are none left
+    pub(crate) fn try_acquire(&mut self, initiator_id: NodeId, now: Instant) -> bool {
+        let Some(limit) = self.limit else {
+            return true;
+        };
+        let burst = f64::from(limit.burst.get());
+        let (tokens, refilled_at) = self.buckets.entry(initiator_id).or_insert((burst, now));
+
+        let elapsed = now.saturating_duration_since(*refilled_at).as_secs_f64();
+        *tokens = (*tokens + elapsed * limit.requests_per_second).min(burst);
+        *refilled_at = now;
+
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl MyDrone {
+    /// Limits how many flood requests of the same initiator are forwarded, with `None` they are
+    /// never limited
+    pub(crate) fn set_flood_rate_limit(&mut self, limit: Option<FloodRateLimit>) {
+        log::info!("flood rate limit set to {limit:?}");
+        self.flood_limiter.set_limit(limit);
+    }
+
+    /// Limits how many flood requests of the same initiator are forwarded. The flood requests over
+    /// the limit are answered right away with a flood response, as if the drone had no other
+    /// neighbors.
+    #[must_use]
+    pub fn with_flood_rate_limit(mut self, limit: FloodRateLimit) -> Self {
+        self.set_flood_rate_limit(Some(limit));
+        self
+    }
+
+    /// Returns how many flood requests have been answered instead of forwarded because their
+    /// initiator went over the flood rate limit, same as the `flood_requests_rate_limited` of
+    /// `MyDrone::stats`
+    #[must_use]
+    pub fn rate_limited_flood_requests(&self) -> u64 {
+        self.stats().flood_requests_rate_limited
+    }
+}
@@ -1,18 +1,80 @@
 use core::panic;
-use crossbeam_channel::{select_biased, Receiver, Sender};
-use std::collections::{HashMap, HashSet};
+use crossbeam_channel::{never, select_biased, unbounded, Receiver, Sender};
+use flood_cache::FloodCache;
+use flood_rate_limit::FloodLimiter;
+use neighbor::Neighbor;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use rng::DroneRng;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
 use wg_2024::controller::{DroneCommand, DroneEvent};
 use wg_2024::drone::Drone;
 use wg_2024::network::NodeId;
 use wg_2024::packet::Packet;
 
+#[cfg(feature = "tokio")]
+mod async_run;
+mod bandwidth;
+mod builder;
+mod clock;
 mod configuration;
+mod control;
+mod drop_model;
+mod error;
+mod executor;
+mod fault_policy;
+mod flood_cache;
+mod flood_rate_limit;
+mod link_delay;
+#[cfg(feature = "prometheus")]
+pub mod metrics;
+mod neighbor;
+pub mod network;
+mod outbound_queue;
 mod packet_processing;
 mod packet_sending;
+mod pdr_schedule;
+mod rng;
+pub mod scenario;
+mod scheduler;
+mod simulation_controller;
+mod stats;
+mod step;
+mod topology;
 
-#[derive(Clone, Copy, Debug)]
-enum State {
+#[cfg(feature = "tokio")]
+pub use async_run::MAX_IDLE_POLL_INTERVAL;
+pub use bandwidth::{
+    packet_size, ExcessPolicy, LinkBandwidth, MAX_PACKET_SIZE, PACKET_HEADER_OVERHEAD,
+};
+pub use builder::MyDroneBuilder;
+pub use clock::{Clock, MockClock, SystemClock};
+pub use control::ControlCommand;
+pub use drop_model::{Bernoulli, DropModel, EveryNth, GilbertElliott, Scripted};
+pub use error::DroneError;
+pub use executor::{DroneExecutor, DroneExit};
+pub use fault_policy::{ErrorClass, FaultAction, FaultPolicy};
+pub use flood_cache::DEFAULT_FLOOD_CACHE_CAPACITY;
+pub use flood_rate_limit::FloodRateLimit;
+pub use link_delay::{DelayModel, LinkDelay};
+pub use outbound_queue::{OverflowPolicy, QueueConfig, DEFAULT_QUEUE_CAPACITY};
+pub use pdr_schedule::PdrSchedule;
+pub use scheduler::{ClassWeights, Scheduler, TrafficClass};
+pub use simulation_controller::{ControllerError, SimulationController};
+pub use stats::{DroneStats, DroneStatsHandle, LinkStats, NackCounts, PacketCounts};
+pub use step::StepOutcome;
+pub use topology::{validate_topology, TopologyIssue, MAX_CLIENT_DRONES, MIN_SERVER_DRONES};
+
+/// Lifecycle of the drone
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum State {
+    /// Processes packets and commands
+    #[default]
     Working,
+    /// Received a `DroneCommand::Crash`, processes the packets left and stops when all the
+    /// senders to its packet channel are dropped
     Crashing,
 }
 
@@ -21,16 +83,35 @@ pub struct MyDrone {
     id: NodeId,
     controller_send: Sender<DroneEvent>,
     controller_recv: Receiver<DroneCommand>,
+    control_send: Sender<ControlCommand>,
+    control_recv: Receiver<ControlCommand>,
     packet_recv: Receiver<Packet>,
     pdr: f32,
-    packet_send: HashMap<NodeId, Sender<Packet>>,
-    known_flood_ids: HashSet<(u64, NodeId)>,
+    packet_send: HashMap<NodeId, Neighbor>,
+    queue_config: QueueConfig,
+    known_flood_ids: FloodCache,
+    flood_limiter: FloodLimiter,
     state: State,
+    fault_policy: FaultPolicy,
+    rng: DroneRng,
+    drop_model: Box<dyn DropModel>,
+    // whether `drop_model` was given with `with_drop_model` instead of following the pdr
+    custom_drop_model: bool,
+    pdr_schedule: Option<(PdrSchedule, Instant)>,
+    scheduler: Scheduler,
+    clock: Arc<dyn Clock>,
+    stats: DroneStatsHandle,
 }
 
 impl Drone for MyDrone {
     /// creates the drone with the given options, logs that it has been created and returns the
     /// drone
+    ///
+    /// # Panics
+    /// - `pdr` is not in range (0.0..=1.0), see `DroneError::InvalidPdr`
+    ///
+    /// - `packet_send` contains a channel with the same `NodeId` of this drone, see
+    ///   `DroneError::SelfChannel`
     fn new(
         id: NodeId,
         controller_send: Sender<DroneEvent>,
@@ -39,28 +120,25 @@ impl Drone for MyDrone {
         packet_send: HashMap<NodeId, Sender<Packet>>,
         pdr: f32,
     ) -> Self {
-        let mut result = Self {
+        Self::with_rng(
             id,
             controller_send,
             controller_recv,
             packet_recv,
-            pdr: 0f32,
-            packet_send: HashMap::new(),
-            known_flood_ids: HashSet::new(),
-            state: State::Working,
-        };
-        result.set_pdr(pdr);
-        for (node_id, channel) in packet_send {
-            result.add_channel(node_id, channel);
-        }
-        log::info!("\"null-pointer-drone\" drone created: {:?}", result);
-        result
+            packet_send,
+            pdr,
+            StdRng::from_os_rng(),
+        )
     }
 
     /// runs the drone loop, listening for events and packets, exits successfully if sent a
     /// `DroneCommand::Crash` after all references of the sender for its own receiver have been
     /// dropped
     ///
+    /// Every anomaly is reported internally as a `DroneError` and handled according to the
+    /// `FaultPolicy` of the drone. With the default strict policy the drone panics with the message
+    /// of the error, these are all the cases:
+    ///
     /// # Panics
     /// - The `Sender<DroneCommand>` end of the simulation controller channel unexpectedly got dropped
     ///
@@ -88,62 +166,139 @@ impl Drone for MyDrone {
     /// - Cannot send event {&event} to simulation controller. Error: {error:?}"
     ///   same thing as above but for event
     fn run(&mut self) {
-        'loop_label: loop {
+        loop {
             /*
                From https://shadow.github.io/docs/rust/crossbeam/channel/macro.select_biased.html
                "If multiple operations are ready at the same time, the operation nearest to the front of the list is always selected"
                So recv(self.controller_recv) needs to come before recv(self.packet_recv)
+               Commands sent through the control channel come right after the ones of the
+               simulation controller
 
                WARNING: select_biased! seems to select a channel even if one end of it is dropped
                         for instance, if I create a drone and drop the Sender<DroneCommand> channel and I keep using the drone,
                         then the drone will always select the recv(self.controller_recv) arm, since it receives an Err().
                         This means that the drone stops working properly in the case of bad channels management.
                         Interestingly, this didn't occur with the select! macro
+
+               The timer fires when a delayed packet has to be released, when a link has bandwidth
+               for its queued packets again, or to retry the packets waiting in the outbound queues
+               of congested links. While a queue with the OverflowPolicy::Block policy is full no
+               packets are read
             */
-            select_biased! {
-                recv(self.controller_recv) -> command_res => {
-                    log::info!("Received controller command: {command_res:?}");
-                    if let Ok(command) = command_res {
-                        match command
-                        {
-                            DroneCommand::AddSender(node_id, sender) => {
-                                self.add_channel(node_id, sender);
-                            },
-                            DroneCommand::SetPacketDropRate(pdr) => {
-                                self.set_pdr(pdr);
-                            },
-                            DroneCommand::Crash => {
-                                self.set_state(State::Crashing);
-                            },
-                            DroneCommand::RemoveSender(node_id_to_be_removed) => {
-                                self.remove_channel(node_id_to_be_removed);
-                            },
-                        }
-                    } else {
-                        panic!("The Sender<DroneCommand> end of the simulation controller channel unexpectedly got dropped");
-                    }
-                },
-                recv(self.packet_recv) -> packet_res => {
-                    log::info!("Received packet: {packet_res:?}");
-                    match packet_res {
-                        Err(_err) => {
-                             match &self.state {
-                                 State::Working => {
-                                     panic!("There is no connected sender to the drone's packet receiver channel and no DroneCommand::Crash has been received")
-                                 },
-                                 State::Crashing => {
-                                    log::info!("Drone is finally crashing, no more packets will be processed");
-                                    break 'loop_label
-                                 },
-                             }
-                        }
-                        Ok(packet) => {
-                            log::info!("Processing packet {packet}");
-                            self.process_packet(packet);
-                        }
-                    }
-                },
+            let timer = self.timer();
+            let packet_recv = if self.accepts_packets() {
+                self.packet_recv.clone()
+            } else {
+                never()
+            };
+            let outcome = select_biased! {
+                recv(self.controller_recv) -> command_res => self.on_command(command_res),
+                recv(self.control_recv) -> control_res => self.on_control_command(control_res),
+                recv(timer) -> _ => self.on_timer_fired(),
+                recv(packet_recv) -> packet_res => self.on_packet(packet_res),
+            };
+            if matches!(outcome, StepOutcome::Finished) {
+                break;
             }
         }
     }
 }
+
+impl MyDrone {
+    /// same as `Drone::new`, but the packet drop decisions are taken with an rng seeded with
+    /// `seed`, so that two drones created with the same seed drop exactly the same fragments
+    ///
+    /// # Panics
+    /// Same as `Drone::new`
+    #[must_use]
+    pub fn with_seed(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+        pdr: f32,
+        seed: u64,
+    ) -> Self {
+        Self::with_rng(
+            id,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            packet_send,
+            pdr,
+            StdRng::seed_from_u64(seed),
+        )
+    }
+
+    /// same as `Drone::new`, but the packet drop decisions are taken with the given `rng`
+    ///
+    /// # Panics
+    /// Same as `Drone::new`
+    #[must_use]
+    pub fn with_rng(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+        pdr: f32,
+        rng: impl RngCore + Send + 'static,
+    ) -> Self {
+        let result = Self::try_new(
+            id,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            packet_send,
+            pdr,
+            DroneRng::new(rng),
+        );
+        match result {
+            Ok(drone) => drone,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    /// Creates the drone, returning an error instead of panicking if `pdr` or `packet_send` are
+    /// not valid
+    pub(crate) fn try_new(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+        pdr: f32,
+        rng: DroneRng,
+    ) -> Result<Self, DroneError> {
+        let (control_send, control_recv) = unbounded();
+        let mut result = Self {
+            id,
+            controller_send,
+            controller_recv,
+            control_send,
+            control_recv,
+            packet_recv,
+            pdr: 0f32,
+            packet_send: HashMap::new(),
+            queue_config: QueueConfig::default(),
+            known_flood_ids: FloodCache::new(DEFAULT_FLOOD_CACHE_CAPACITY, None),
+            flood_limiter: FloodLimiter::default(),
+            state: State::Working,
+            fault_policy: FaultPolicy::strict(),
+            rng,
+            drop_model: Box::new(Bernoulli::default()),
+            custom_drop_model: false,
+            pdr_schedule: None,
+            scheduler: Scheduler::default(),
+            clock: Arc::new(SystemClock),
+            stats: DroneStatsHandle::default(),
+        };
+        result.set_pdr(pdr)?;
+        for (node_id, channel) in packet_send {
+            result.add_channel(node_id, channel)?;
+        }
+        log::info!("\"null-pointer-drone\" drone created: {:?}", result);
+        Ok(result)
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{DroneError, MyDrone};
+use rand::{Rng, RngCore};
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// How long a packet takes to go through a link
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DelayModel {
+    /// Every packet takes the same time
+    Fixed(Duration),
+    /// Uniformly distributed between `min` and `max`
+    Uniform { min: Duration, max: Duration },
+    /// Normally distributed, the negative samples are clamped to zero
+    Normal { mean: Duration, std_dev: Duration },
+}
+
+/// How many standard deviations away from the mean the delays of `DelayModel::Normal` must still
+/// be representable, the samples further away are so unlikely that they are reported as errors
+const NORMAL_TAIL: f64 = 10.0;
+
+impl DelayModel {
+    /// # Errors
+    /// Returns `DroneError::InvalidDelay` if `min` is greater than `max`
+    pub fn uniform(min: Duration, max: Duration) -> Result<Self, DroneError> {
+        let model = Self::Uniform { min, max };
+        model.validate()?;
+        Ok(model)
+    }
+
+    /// # Errors
+    /// Returns `DroneError::InvalidDelay` if the delays up to 10 standard deviations above the
+    /// mean do not fit in a `Duration`
+    pub fn normal(mean: Duration, std_dev: Duration) -> Result<Self, DroneError> {
+        let model = Self::Normal { mean, std_dev };
+        model.validate()?;
+        Ok(model)
+    }
+
+    /// Checks the parameters of the model, which can be built without `DelayModel::uniform` and
+    /// `DelayModel::normal` since its fields are public
+    /// # Errors
+    /// Same as `DelayModel::uniform` and `DelayModel::normal`
+    pub fn validate(&self) -> Result<(), DroneError> {
+        match *self {
+            DelayModel::Fixed(_) => Ok(()),
+            DelayModel::Uniform { min, max } if min > max => Err(DroneError::InvalidDelay(
+                format!("the minimum delay {min:?} is greater than the maximum {max:?}"),
+            )),
+            DelayModel::Uniform { .. } => Ok(()),
+            DelayModel::Normal { mean, std_dev } => {
+                let tail = mean.as_secs_f64() + NORMAL_TAIL * std_dev.as_secs_f64();
+                match Duration::try_from_secs_f64(tail) {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err(DroneError::InvalidDelay(format!(
+                        "a mean of {mean:?} with a standard deviation of {std_dev:?} gives delays \
+                         which do not fit in a Duration"
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// Samples the delay of a packet
+    /// # Errors
+    /// Returns `DroneError::InvalidDelay` if the sample does not fit in a `Duration`
+    pub fn sample(&self, rng: &mut dyn RngCore) -> Result<Duration, DroneError> {
+        let seconds = match *self {
+            DelayModel::Fixed(delay) => return Ok(delay),
+            DelayModel::Uniform { min, max } => {
+                rng.random_range(min.as_secs_f64()..=max.as_secs_f64())
+            }
+            DelayModel::Normal { mean, std_dev } => {
+                // Box-Muller transform, 1 - u is in (0, 1] so that its logarithm is finite
+                let u: f64 = rng.random();
+                let v: f64 = rng.random();
+                let z = (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos();
+                let delay = mean.as_secs_f64() + z * std_dev.as_secs_f64();
+                delay.max(0.0)
+            }
+        };
+        Duration::try_from_secs_f64(seconds).map_err(|_| {
+            DroneError::InvalidDelay(format!("a delay of {seconds} seconds is too long"))
+        })
+    }
+}
+
+/// Delay of the packets sent on the link to a neighbor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinkDelay {
+    pub model: DelayModel,
+    /// When `false` a packet with a shorter delay can overtake the ones sent before it
+    pub preserve_order: bool,
+}
+
+impl LinkDelay {
+    /// Delays the packets according to `model`, keeping them in order
+    #[must_use]
+    pub fn new(model: DelayModel) -> Self {
+        Self {
+            model,
+            preserve_order: true,
+        }
+    }
+
+    /// Lets the packets with a shorter delay overtake the ones sent before them
+    #[must_use]
+    pub fn allow_reordering(mut self) -> Self {
+        self.preserve_order = false;
+        self
+    }
+}
+
+impl MyDrone {
+    /// Delays the packets sent to `node_id`, with `None` they are sent right away. The delay is
+    /// forgotten when the channel to `node_id` is removed.
+    /// # Errors
+    /// Returns `DroneError::InvalidDelay` if the parameters of the delay model are invalid, see
+    /// `DelayModel::validate`, `DroneError::UnknownNeighbor` if `node_id` is not a neighbor
+    pub fn set_neighbor_delay(
+        &mut self,
+        node_id: NodeId,
+        delay: Option<LinkDelay>,
+    ) -> Result<(), DroneError> {
+        if let Some(delay) = delay {
+            delay.model.validate()?;
+        }
+        let Some(neighbor) = self.packet_send.get_mut(&node_id) else {
+            return Err(DroneError::UnknownNeighbor(node_id));
+        };
+        neighbor.delay = delay;
+        log::info!("delay of the link to {node_id} set to {delay:?}");
+        Ok(())
+    }
+
+    /// Holds the packet for the delay of the link to `dest` if it has one, otherwise puts it in
+    /// the outbound queue right away
+    /// # Errors
+    /// Returns `DroneError::InvalidDelay` if the delay or the release time cannot be represented,
+    /// the errors of `MyDrone::enqueue_packet`
+    pub(crate) fn delay_packet(&mut self, dest: NodeId, packet: Packet) -> Result<(), DroneError> {
+        let Some(delay) = self
+            .packet_send
+            .get(&dest)
+            .and_then(|neighbor| neighbor.delay)
+        else {
+            return self.enqueue_packet(dest, packet);
+        };
+
+        let sampled = delay.model.sample(&mut *self.rng)?;
+        let Some(mut release_at) = self.clock.now().checked_add(sampled) else {
+            return Err(DroneError::InvalidDelay(format!(
+                "a delay of {sampled:?} goes past the end of time"
+            )));
+        };
+        let Some(neighbor) = self.packet_send.get_mut(&dest) else {
+            return Err(DroneError::UnknownNeighbor(dest));
+        };
+        if delay.preserve_order {
+            if let Some(((last_release, _seq), _packet)) = neighbor.delayed.last_key_value() {
+                release_at = release_at.max(*last_release);
+            }
+        }
+        log::debug!("delaying packet {packet} to {dest} until {release_at:?}");
+        neighbor
+            .delayed
+            .insert((release_at, neighbor.next_delay_seq), packet);
+        neighbor.next_delay_seq += 1;
+        Ok(())
+    }
+
+    /// Returns when the next delayed packet has to be released
+    pub(crate) fn next_delayed_release(&self) -> Option<Instant> {
+        self.packet_send
+            .values()
+            .filter_map(|neighbor| neighbor.delayed.first_key_value())
+            .map(|((release_at, _seq), _packet)| *release_at)
+            .min()
+    }
+
+    /// Moves the delayed packets whose delay is over to the outbound queues
+    /// # Errors
+    /// Returns the first error of `MyDrone::enqueue_packet`, the other packets are released later
+    pub(crate) fn release_delayed_packets(&mut self, now: Instant) -> Result<(), DroneError> {
+        let node_ids: Vec<NodeId> = self.packet_send.keys().copied().collect();
+        for node_id in node_ids {
+            while let Some(neighbor) = self.packet_send.get_mut(&node_id) {
+                let Some(entry) = neighbor.delayed.first_entry() else {
+                    break;
+                };
+                if entry.key().0 > now {
+                    break;
+                }
+                let packet = entry.remove();
+                self.enqueue_packet(node_id, packet)?;
+            }
+        }
+        Ok(())
+    }
+}
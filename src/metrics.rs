@@ -0,0 +1,340 @@
+//! Prometheus exposition of the statistics of the drones, enabled by the `prometheus` feature
+
+use crate::{DroneStats, DroneStatsHandle, LinkStats, PacketCounts, State};
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use wg_2024::network::NodeId;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+/// How long a connection can take to send its request, it only holds its own thread
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// name, help and value of a metric family
+type PacketFamily = (&'static str, &'static str, fn(&DroneStats) -> &PacketCounts);
+type LinkFamily = (&'static str, &'static str, fn(&LinkStats) -> u64);
+/// name, help, type and value of a metric family
+type DroneFamily = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&DroneStats) -> String,
+);
+
+/// Renders the statistics of the given drones in the Prometheus text exposition format
+#[must_use]
+pub fn render(drones: &[(NodeId, DroneStats)]) -> String {
+    let mut out = String::new();
+
+    let packet_families: [PacketFamily; 4] = [
+        (
+            "npd_packets_received_total",
+            "Packets received from the neighbors",
+            |stats| &stats.received,
+        ),
+        (
+            "npd_packets_forwarded_total",
+            "Packets sent to a neighbor",
+            |stats| &stats.forwarded,
+        ),
+        (
+            "npd_packets_dropped_total",
+            "Packets dropped by the drop model, the pdr or bandwidth of a link, or a full queue",
+            |stats| &stats.dropped,
+        ),
+        (
+            "npd_packets_shortcut_total",
+            "Packets sent to the simulation controller",
+            |stats| &stats.shortcut,
+        ),
+    ];
+    for (name, help, counts) in packet_families {
+        header(&mut out, name, help, "counter");
+        for (id, stats) in drones {
+            let counts = counts(stats);
+            for (packet_type, value) in [
+                ("fragment", counts.fragment),
+                ("ack", counts.ack),
+                ("nack", counts.nack),
+                ("flood_request", counts.flood_request),
+                ("flood_response", counts.flood_response),
+            ] {
+                sample(&mut out, name, *id, Some(("type", packet_type)), value);
+            }
+        }
+    }
+
+    let name = "npd_nacks_total";
+    header(&mut out, name, "Nacks created by the drone", "counter");
+    for (id, stats) in drones {
+        let nacked = &stats.nacked;
+        for (nack_type, value) in [
+            ("error_in_routing", nacked.error_in_routing),
+            ("destination_is_drone", nacked.destination_is_drone),
+            ("dropped", nacked.dropped),
+            ("unexpected_recipient", nacked.unexpected_recipient),
+        ] {
+            sample(&mut out, name, *id, Some(("nack_type", nack_type)), value);
+        }
+    }
+
+    let name = "npd_flood_requests_total";
+    header(
+        &mut out,
+        name,
+        "Flood requests by outcome, the rate limited ones are also answered",
+        "counter",
+    );
+    for (id, stats) in drones {
+        for (outcome, value) in [
+            ("forwarded", stats.flood_requests_forwarded),
+            ("answered", stats.flood_requests_answered),
+            ("rate_limited", stats.flood_requests_rate_limited),
+        ] {
+            sample(&mut out, name, *id, Some(("outcome", outcome)), value);
+        }
+    }
+
+    let drone_families: [DroneFamily; 4] = [
+        (
+            "npd_flood_cache_size",
+            "Floods remembered by the flood cache",
+            "gauge",
+            |stats| stats.flood_cache_len.to_string(),
+        ),
+        (
+            "npd_flood_cache_evictions_total",
+            "Floods evicted from the flood cache",
+            "counter",
+            |stats| stats.flood_cache_evictions.to_string(),
+        ),
+        (
+            "npd_neighbors",
+            "Neighbors of the drone",
+            "gauge",
+            |stats| stats.neighbors().to_string(),
+        ),
+        ("npd_pdr", "Pdr of the drone", "gauge", |stats| {
+            stats.pdr.to_string()
+        }),
+    ];
+    for (name, help, metric_type, value) in drone_families {
+        header(&mut out, name, help, metric_type);
+        for (id, stats) in drones {
+            sample(&mut out, name, *id, None, value(stats));
+        }
+    }
+
+    let name = "npd_state";
+    header(
+        &mut out,
+        name,
+        "1 for the current state of the drone",
+        "gauge",
+    );
+    for (id, stats) in drones {
+        for (state, label) in [(State::Working, "working"), (State::Crashing, "crashing")] {
+            let value = u8::from(stats.state == state);
+            sample(&mut out, name, *id, Some(("state", label)), value);
+        }
+    }
+
+    let link_families: [LinkFamily; 6] = [
+        (
+            "npd_link_packets_total",
+            "Packets sent on the link to a neighbor",
+            |link| link.packets,
+        ),
+        (
+            "npd_link_bytes_total",
+            "Bytes of the fragments sent on the link to a neighbor, without headers",
+            |link| link.bytes,
+        ),
+        (
+            "npd_link_failures_total",
+            "Packets which could not be sent on the link to a neighbor",
+            |link| link.failures,
+        ),
+        (
+            "npd_link_drops_total",
+            "Fragments and flood requests dropped instead of being sent to a neighbor",
+            |link| link.drops,
+        ),
+        (
+            "npd_link_shaped_wire_bytes_total",
+            "Bytes with headers held back by the bandwidth limit of the link to a neighbor",
+            |link| link.shaped_wire_bytes,
+        ),
+        (
+            "npd_link_dropped_wire_bytes_total",
+            "Bytes with headers of the packets dropped instead of being sent to a neighbor",
+            |link| link.dropped_wire_bytes,
+        ),
+    ];
+    for (name, help, value) in link_families {
+        header(&mut out, name, help, "counter");
+        for (id, stats) in drones {
+            let mut links: Vec<_> = stats.links.iter().collect();
+            links.sort_by_key(|(neighbor, _link)| **neighbor);
+            for (neighbor, link) in links {
+                let neighbor = neighbor.to_string();
+                sample(
+                    &mut out,
+                    name,
+                    *id,
+                    Some(("neighbor", &neighbor)),
+                    value(link),
+                );
+            }
+        }
+    }
+
+    out
+}
+
+fn header(out: &mut String, name: &str, help: &str, metric_type: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {metric_type}");
+}
+
+fn sample(
+    out: &mut String,
+    name: &str,
+    drone: NodeId,
+    label: Option<(&str, &str)>,
+    value: impl std::fmt::Display,
+) {
+    match label {
+        Some((label, label_value)) => {
+            let _ = writeln!(
+                out,
+                "{name}{{drone=\"{drone}\",{label}=\"{label_value}\"}} {value}"
+            );
+        }
+        None => {
+            let _ = writeln!(out, "{name}{{drone=\"{drone}\"}} {value}");
+        }
+    }
+}
+
+/// Tiny HTTP server which serves the statistics of a set of drones on `/metrics`, in the
+/// Prometheus text exposition format. Every connection is served on its own thread, so a slow
+/// client does not hold back the others. It stops accepting connections when dropped.
+#[derive(Debug)]
+pub struct MetricsServer {
+    local_addr: SocketAddr,
+    stopping: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    /// Starts serving the statistics of `drones` on `addr`, a loopback address like
+    /// `127.0.0.1:9898`. With port 0 a free port is chosen, see `MetricsServer::local_addr`.
+    /// # Errors
+    /// Returns an `io::ErrorKind::InvalidInput` error if `addr` is not a loopback address, the
+    /// errors of `TcpListener::bind` otherwise
+    pub fn start(
+        addr: impl ToSocketAddrs,
+        drones: impl IntoIterator<Item = (NodeId, DroneStatsHandle)>,
+    ) -> io::Result<Self> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        if let Some(addr) = addrs.iter().find(|addr| !addr.ip().is_loopback()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the metrics are only served on loopback addresses, not on {addr}"),
+            ));
+        }
+        let listener = TcpListener::bind(addrs.as_slice())?;
+        let local_addr = listener.local_addr()?;
+        let mut drones: Vec<_> = drones.into_iter().collect();
+        drones.sort_by_key(|(id, _stats)| *id);
+        let drones = Arc::new(drones);
+        let stopping = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let stopping = Arc::clone(&stopping);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopping.load(Ordering::Acquire) {
+                        break;
+                    }
+                    let spawned = stream.and_then(|stream| {
+                        let drones = Arc::clone(&drones);
+                        thread::Builder::new()
+                            .name("metrics-connection".to_string())
+                            .spawn(move || {
+                                if let Err(error) = serve(stream, &drones) {
+                                    log::warn!("metrics server: {error}");
+                                }
+                            })
+                    });
+                    if let Err(error) = spawned {
+                        log::warn!("metrics server: {error}");
+                    }
+                }
+            })
+        };
+        log::info!("serving metrics on http://{local_addr}/metrics");
+
+        Ok(Self {
+            local_addr,
+            stopping,
+            handle: Some(handle),
+        })
+    }
+
+    /// Returns the address the server is listening on
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::Release);
+        // wakes up the blocking accept of the server thread
+        let _ = TcpStream::connect(self.local_addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn serve(stream: TcpStream, drones: &[(NodeId, DroneStatsHandle)]) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // the headers are not used, but they are read so that the client does not see a reset
+    let mut header_line = String::new();
+    while reader.read_line(&mut header_line)? > 2 {
+        header_line.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let stats: Vec<_> = drones
+                .iter()
+                .map(|(id, stats)| (*id, stats.snapshot()))
+                .collect();
+            ("200 OK", render(&stats))
+        }
+        (Some("GET"), Some(_path)) => ("404 Not Found", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "method not allowed\n".to_string()),
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,38 @@
+use crate::bandwidth::Shaper;
+use crate::scheduler::ClassQueues;
+use crate::LinkDelay;
+use crossbeam_channel::Sender;
+use std::collections::BTreeMap;
+use std::time::Instant;
+use wg_2024::packet::Packet;
+
+/// Everything the drone knows about the link to one of its neighbors
+#[derive(Debug, Clone)]
+pub(crate) struct Neighbor {
+    pub(crate) sender: Sender<Packet>,
+    /// pdr of this link only, when `None` the drop model of the drone is used
+    pub(crate) pdr: Option<f32>,
+    /// packets waiting for room in the channel, sent in the order given by the scheduler
+    pub(crate) queue: ClassQueues,
+    /// delay of this link, when `None` packets are sent right away
+    pub(crate) delay: Option<LinkDelay>,
+    /// packets waiting for their delay to be over, by release time and arrival order
+    pub(crate) delayed: BTreeMap<(Instant, u64), Packet>,
+    pub(crate) next_delay_seq: u64,
+    /// bandwidth limit of this link, when `None` it is unlimited
+    pub(crate) shaper: Option<Shaper>,
+}
+
+impl Neighbor {
+    pub(crate) fn new(sender: Sender<Packet>) -> Self {
+        Self {
+            sender,
+            pdr: None,
+            queue: ClassQueues::default(),
+            delay: None,
+            delayed: BTreeMap::new(),
+            next_delay_seq: 0,
+            shaper: None,
+        }
+    }
+}
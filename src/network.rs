@@ -0,0 +1,200 @@
+//! Network initialization from the WG-2024 topology file: parses it, validates it, creates the
+//! channels of every node and builds the drones
+
+use crate::topology::links;
+use crate::{validate_topology, DroneError, MyDrone, MyDroneBuilder, TopologyIssue};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::Path;
+use std::thread::{self, JoinHandle};
+use wg_2024::config::{self, Config};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Everything that can go wrong while setting up a network
+#[derive(Debug)]
+pub enum NetworkError {
+    /// The topology file cannot be read
+    Io(io::Error),
+    /// The topology file is not valid TOML or does not follow the format of `Config`
+    Parse(toml::de::Error),
+    /// The topology is not a valid network
+    Topology(Vec<TopologyIssue>),
+    /// A drone refused its configuration
+    Drone(NodeId, DroneError),
+    /// The thread of a drone cannot be spawned
+    Spawn(NodeId, io::Error),
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkError::Io(error) => write!(f, "Cannot read the topology file: {error}"),
+            NetworkError::Parse(error) => write!(f, "Cannot parse the topology file: {error}"),
+            NetworkError::Topology(issues) => {
+                write!(f, "Invalid topology:")?;
+                for issue in issues {
+                    write!(f, "\n- {issue}")?;
+                }
+                Ok(())
+            }
+            NetworkError::Drone(id, error) => write!(f, "Cannot build drone {id}: {error}"),
+            NetworkError::Spawn(id, error) => {
+                write!(f, "Cannot spawn the thread of drone {id}: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// The channels the simulation controller uses to talk to a drone
+#[derive(Debug, Clone)]
+pub struct DroneHandle {
+    pub command_send: Sender<DroneCommand>,
+    pub event_recv: Receiver<DroneEvent>,
+    /// Sender to the packet channel of the drone, to deliver the packets shortcut to it and to
+    /// connect new neighbors with `DroneCommand::AddSender`
+    pub packet_send: Sender<Packet>,
+}
+
+/// The channels of a client or a server, which are left to your own implementation
+#[derive(Debug, Clone)]
+pub struct HostHandle {
+    pub packet_recv: Receiver<Packet>,
+    /// Senders to the packet channels of the drones connected to the host
+    pub neighbors: HashMap<NodeId, Sender<Packet>>,
+    /// Sender to the packet channel of the host, to deliver the packets shortcut to it
+    pub packet_send: Sender<Packet>,
+}
+
+/// The channels of every node of a network
+#[derive(Debug, Clone, Default)]
+pub struct Network {
+    pub drones: HashMap<NodeId, DroneHandle>,
+    pub clients: HashMap<NodeId, HostHandle>,
+    pub servers: HashMap<NodeId, HostHandle>,
+}
+
+/// Reads the topology file at `path`
+/// # Errors
+/// `NetworkError::Io` if the file cannot be read, `NetworkError::Parse` if it is malformed
+pub fn load(path: impl AsRef<Path>) -> Result<Config, NetworkError> {
+    let text = std::fs::read_to_string(path).map_err(NetworkError::Io)?;
+    parse(&text)
+}
+
+/// Parses a topology in the TOML format of the WG-2024 network initializer
+/// # Errors
+/// `NetworkError::Parse` if the text is malformed
+pub fn parse(text: &str) -> Result<Config, NetworkError> {
+    toml::from_str(text).map_err(NetworkError::Parse)
+}
+
+/// Checks the topology with `validate_topology`
+/// # Errors
+/// Every problem of the topology
+pub fn validate(config: &Config) -> Result<(), Vec<TopologyIssue>> {
+    let issues = validate_topology(config);
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(issues)
+    }
+}
+
+/// Validates the topology and creates the channels of every node, returning them together with
+/// the drones, which are not running yet: spawn them, give them to a `DroneExecutor`, or drive
+/// them with `MyDrone::step`
+/// # Errors
+/// `NetworkError::Topology` if the topology is not valid, `NetworkError::Drone` if a drone cannot
+/// be built
+pub fn init(config: &Config) -> Result<(Network, Vec<MyDrone>), NetworkError> {
+    init_with(config, |_, builder| builder)
+}
+
+/// Same as `init`, but every drone is configured by `configure` before being built
+/// # Errors
+/// Same as `init`
+pub fn init_with(
+    config: &Config,
+    mut configure: impl FnMut(&config::Drone, MyDroneBuilder) -> MyDroneBuilder,
+) -> Result<(Network, Vec<MyDrone>), NetworkError> {
+    validate(config).map_err(NetworkError::Topology)?;
+    let links = links(config);
+    let packet_channels: HashMap<NodeId, (Sender<Packet>, Receiver<Packet>)> =
+        links.iter().map(|(id, _)| (*id, unbounded())).collect();
+    let neighbors = |node_neighbors: &[NodeId]| -> HashMap<NodeId, Sender<Packet>> {
+        node_neighbors
+            .iter()
+            .map(|neighbor| (*neighbor, packet_channels[neighbor].0.clone()))
+            .collect()
+    };
+
+    let mut network = Network::default();
+    let mut drones = Vec::with_capacity(config.drone.len());
+    for drone in &config.drone {
+        let (command_send, command_recv) = unbounded();
+        let (event_send, event_recv) = unbounded();
+        let (packet_send, packet_recv) = packet_channels[&drone.id].clone();
+        let builder = MyDrone::builder(
+            drone.id,
+            event_send,
+            command_recv,
+            packet_recv,
+            neighbors(&drone.connected_node_ids),
+        )
+        .pdr(drone.pdr);
+        let my_drone = configure(drone, builder)
+            .build()
+            .map_err(|error| NetworkError::Drone(drone.id, error))?;
+        drones.push(my_drone);
+        network.drones.insert(
+            drone.id,
+            DroneHandle {
+                command_send,
+                event_recv,
+                packet_send,
+            },
+        );
+    }
+    let host = |id: NodeId, connected_drone_ids: &[NodeId]| {
+        let (packet_send, packet_recv) = packet_channels[&id].clone();
+        HostHandle {
+            packet_recv,
+            neighbors: neighbors(connected_drone_ids),
+            packet_send,
+        }
+    };
+    for client in &config.client {
+        let handle = host(client.id, &client.connected_drone_ids);
+        network.clients.insert(client.id, handle);
+    }
+    for server in &config.server {
+        let handle = host(server.id, &server.connected_drone_ids);
+        network.servers.insert(server.id, handle);
+    }
+    Ok((network, drones))
+}
+
+/// Same as `init`, with every drone running `Drone::run` on its own thread
+/// # Errors
+/// Same as `init`, or `NetworkError::Spawn` if a thread cannot be spawned
+pub fn start(config: &Config) -> Result<(Network, Vec<JoinHandle<()>>), NetworkError> {
+    let (network, drones) = init(config)?;
+    let threads = drones
+        .into_iter()
+        .map(|mut drone| {
+            let id = drone.id;
+            thread::Builder::new()
+                .name(format!("drone-{id}"))
+                .spawn(move || drone.run())
+                .map_err(|error| NetworkError::Spawn(id, error))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((network, threads))
+}
@@ -0,0 +1,283 @@
+use crate::bandwidth::packet_size;
+use crate::{DroneError, MyDrone};
+use crossbeam_channel::{after, never, Receiver, SendError, TrySendError};
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+/// Default number of packets waiting to be sent to each neighbor
+pub const DEFAULT_QUEUE_CAPACITY: NonZeroUsize = match NonZeroUsize::new(1024) {
+    Some(capacity) => capacity,
+    None => unreachable!(),
+};
+
+/// How often the drone retries to send the packets waiting in the queues of congested links
+pub(crate) const QUEUE_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+/// What the drone does with a packet for a neighbor whose outbound queue is full
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Stops reading packets from its own channel until the queue has room again, leaving the
+    /// congestion to the nodes that send packets to this drone. Commands are still processed.
+    #[default]
+    Block,
+    /// Drops the new packet
+    DropTail,
+    /// Drops the oldest packet of the lowest priority `TrafficClass` in the queue to make room for
+    /// the new one
+    DropHead,
+}
+
+/// Capacity and overflow policy of the outbound queue of every neighbor.
+///
+/// Packets are sent with `try_send`, so a neighbor with a full bounded channel never blocks the
+/// drone: its packets wait in the queue and are retried until the channel has room. Dropped
+/// fragments are answered with a `NackType::Dropped` nack, the other packets, which cannot be
+/// dropped, are sent to the simulation controller as a `DroneEvent::ControllerShortcut`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueueConfig {
+    pub capacity: NonZeroUsize,
+    pub overflow: OverflowPolicy,
+}
+
+impl QueueConfig {
+    #[must_use]
+    pub fn new(capacity: NonZeroUsize, overflow: OverflowPolicy) -> Self {
+        Self { capacity, overflow }
+    }
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_QUEUE_CAPACITY, OverflowPolicy::default())
+    }
+}
+
+impl MyDrone {
+    /// Sets the capacity and overflow policy of the outbound queues of all the links
+    #[must_use]
+    pub fn with_outbound_queue(mut self, queue_config: QueueConfig) -> Self {
+        log::info!("outbound queues set to {queue_config:?}");
+        self.queue_config = queue_config;
+        self
+    }
+
+    /// Puts the packet in the outbound queue of `dest`, which must be a neighbor, applying the
+    /// overflow policy if the queue is still full after trying to send what is in it. If the link
+    /// drops its excess traffic, the packets over its bandwidth are dropped instead.
+    /// # Errors
+    /// Returns the errors of `MyDrone::flush_queue` and of the nack sent for a dropped packet
+    pub(crate) fn enqueue_packet(
+        &mut self,
+        dest: NodeId,
+        packet: Packet,
+    ) -> Result<(), DroneError> {
+        if !self.admit_packet(dest, &packet) {
+            log::warn!("bandwidth of the link to {dest} exceeded, dropping packet {packet}");
+            return self.drop_queued_packet(dest, packet);
+        }
+
+        let QueueConfig { capacity, overflow } = self.queue_config;
+        self.flush_queue(dest)?;
+        let Some(neighbor) = self.packet_send.get_mut(&dest) else {
+            return Err(DroneError::UnknownNeighbor(dest));
+        };
+
+        let mut dropped = None;
+        if neighbor.queue.len() >= capacity.get() {
+            match overflow {
+                // with this policy the drone stops reading packets when a queue is full, so the
+                // queue only goes over capacity when packets are processed by hand
+                OverflowPolicy::Block => neighbor.queue.push_back(packet),
+                OverflowPolicy::DropTail => dropped = Some(packet),
+                OverflowPolicy::DropHead => {
+                    dropped = neighbor.queue.pop_lowest_priority();
+                    neighbor.queue.push_back(packet);
+                    if let Some(shaper) = &mut neighbor.shaper {
+                        shaper.holding = false;
+                    }
+                }
+            }
+        } else {
+            neighbor.queue.push_back(packet);
+        }
+
+        if let Some(packet) = dropped {
+            log::warn!("outbound queue to {dest} is full, dropping packet {packet}");
+            self.drop_queued_packet(dest, packet)?;
+        }
+        self.flush_queue(dest)
+    }
+
+    /// Sends the packets waiting in the queue of `node_id` until the channel is full or the link
+    /// runs out of bandwidth
+    /// # Errors
+    /// Returns `DroneError::PacketSendFailed` if the channel is disconnected, and the errors of
+    /// `MyDrone::send_event`
+    pub(crate) fn flush_queue(&mut self, node_id: NodeId) -> Result<(), DroneError> {
+        let scheduler = self.scheduler;
+        let now = self.clock.now();
+        loop {
+            let Some(neighbor) = self.packet_send.get_mut(&node_id) else {
+                return Ok(());
+            };
+            let Some(size) = neighbor.queue.front(scheduler).map(packet_size) else {
+                return Ok(());
+            };
+            let shaper = neighbor
+                .shaper
+                .as_mut()
+                .filter(|shaper| shaper.queues_excess());
+            if let Some(shaper) = shaper {
+                if !shaper.try_take(size, now) {
+                    if !shaper.holding {
+                        shaper.holding = true;
+                        self.record(|stats| {
+                            if let Some(link) = stats.links.get_mut(&node_id) {
+                                link.shaped_wire_bytes += u64::from(size);
+                            }
+                        });
+                    }
+                    return Ok(());
+                }
+                shaper.holding = false;
+            }
+            let Some(packet) = neighbor.queue.pop_front(scheduler) else {
+                return Ok(());
+            };
+            match neighbor.sender.try_send(packet.clone()) {
+                Ok(()) => self.on_packet_sent(node_id, &packet)?,
+                Err(TrySendError::Full(packet)) => {
+                    if let Some(shaper) = neighbor
+                        .shaper
+                        .as_mut()
+                        .filter(|shaper| shaper.queues_excess())
+                    {
+                        shaper.refund(size);
+                    }
+                    neighbor.queue.push_front(packet, scheduler);
+                    return Ok(());
+                }
+                Err(TrySendError::Disconnected(packet)) => {
+                    let channel = neighbor.sender.clone();
+                    self.record(|stats| {
+                        if let Some(link) = stats.links.get_mut(&node_id) {
+                            link.failures += 1;
+                        }
+                    });
+                    return Err(DroneError::PacketSendFailed {
+                        channel,
+                        error: Box::new(SendError(packet)),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Retries to send the packets waiting in all the queues
+    /// # Errors
+    /// Returns the first error of `MyDrone::flush_queue`, the other queues are retried later
+    pub(crate) fn flush_queues(&mut self) -> Result<(), DroneError> {
+        let congested: Vec<NodeId> = self
+            .packet_send
+            .iter()
+            .filter(|(_node_id, neighbor)| !neighbor.queue.is_empty())
+            .map(|(node_id, _neighbor)| *node_id)
+            .collect();
+        for node_id in congested {
+            self.flush_queue(node_id)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the time at which the drone has to release delayed packets or send the packets
+    /// waiting for the bandwidth of their link, `None` if there are none
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        let scheduler = self.scheduler;
+        let delayed = self.next_delayed_release();
+        let shaped = self.packet_send.values().filter_map(|neighbor| {
+            let size = packet_size(neighbor.queue.front(scheduler)?);
+            let shaper = neighbor.shaper.as_ref()?;
+            shaper.queues_excess().then(|| shaper.ready_at(size))
+        });
+        shaped.chain(delayed).min()
+    }
+
+    /// Returns `true` if some packets are waiting for room in the channel of their neighbor,
+    /// rather than for the bandwidth of the link
+    pub(crate) fn is_congested(&self) -> bool {
+        let scheduler = self.scheduler;
+        let now = self.clock.now();
+        self.packet_send.values().any(|neighbor| {
+            let Some(size) = neighbor.queue.front(scheduler).map(packet_size) else {
+                return false;
+            };
+            match &neighbor.shaper {
+                Some(shaper) if shaper.queues_excess() => shaper.ready_at(size) <= now,
+                _ => true,
+            }
+        })
+    }
+
+    /// Returns a channel which receives a message when the drone has something to do besides
+    /// processing commands and packets, see `MyDrone::on_timer`. The deadlines are given by the
+    /// clock of the drone, while congested queues are retried after `QUEUE_RETRY_INTERVAL` of real
+    /// time since they wait for the neighbors.
+    pub(crate) fn timer(&mut self) -> Receiver<Instant> {
+        if self.is_congested() {
+            return after(QUEUE_RETRY_INTERVAL);
+        }
+        match self.next_deadline() {
+            Some(deadline) => self.clock.at(deadline),
+            None => never(),
+        }
+    }
+
+    /// Releases the delayed packets whose delay is over and retries the queued ones
+    /// # Errors
+    /// Returns the errors of `MyDrone::release_delayed_packets` and `MyDrone::flush_queues`
+    pub(crate) fn on_timer(&mut self) -> Result<(), DroneError> {
+        self.release_delayed_packets(self.clock.now())?;
+        self.flush_queues()
+    }
+
+    /// Returns `false` if the drone should stop reading packets because of a full queue with the
+    /// `OverflowPolicy::Block` policy
+    pub(crate) fn accepts_packets(&self) -> bool {
+        let QueueConfig { capacity, overflow } = self.queue_config;
+        overflow != OverflowPolicy::Block
+            || self
+                .packet_send
+                .values()
+                .all(|neighbor| neighbor.queue.len() < capacity.get())
+    }
+
+    fn drop_queued_packet(&mut self, dest: NodeId, mut packet: Packet) -> Result<(), DroneError> {
+        if matches!(
+            packet.pack_type,
+            PacketType::MsgFragment(_) | PacketType::FloodRequest(_)
+        ) {
+            self.record(|stats| {
+                stats.dropped.count(&packet.pack_type);
+                if let Some(link) = stats.links.get_mut(&dest) {
+                    link.drops += 1;
+                    link.dropped_wire_bytes += u64::from(packet_size(&packet));
+                }
+            });
+        }
+        match &packet.pack_type {
+            PacketType::MsgFragment(_) => {
+                packet.routing_header.hop_index -= 1;
+                self.make_and_send_nack(&packet, packet.routing_header.hop_index, NackType::Dropped)
+            }
+            // flood requests reach the other drones through the other paths of the flood
+            PacketType::FloodRequest(_) => Ok(()),
+            _ => {
+                self.record(|stats| stats.shortcut.count(&packet.pack_type));
+                self.send_event(&DroneEvent::ControllerShortcut(packet))
+            }
+        }
+    }
+}
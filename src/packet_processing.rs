@@ -1,48 +1,47 @@
-use crate::MyDrone;
+use crate::{DroneError, MyDrone};
 use wg_2024::network::{NodeId, SourceRoutingHeader};
 use wg_2024::packet::{FloodRequest, FloodResponse, NackType, NodeType, Packet, PacketType};
 
 // packet processing section
 impl MyDrone {
-    pub fn process_packet(&mut self, packet: Packet) {
+    /// processes a packet received from a neighbor, forwarding it or answering it as needed
+    /// # Errors
+    /// Returns a `DroneError` if the packet is malformed or if sending fails
+    pub fn process_packet(&mut self, packet: Packet) -> Result<(), DroneError> {
+        self.record(|stats| stats.received.count(&packet.pack_type));
         match packet.pack_type {
             PacketType::FloodRequest(flood_request) => {
-                self.process_flood_request(flood_request, packet.session_id);
+                self.process_flood_request(flood_request, packet.session_id)
             }
             _ => self.process_not_flood_request(packet),
         }
     }
 
-    fn process_not_flood_request(&mut self, mut packet: Packet) {
+    fn process_not_flood_request(&mut self, mut packet: Packet) -> Result<(), DroneError> {
         // you never know what could happen
         debug_assert!(!matches!(packet.pack_type, PacketType::FloodRequest(_)));
 
         let current_index = packet.routing_header.hop_index;
 
-        assert!(
-            !packet.routing_header.is_empty(),
-            "empty routing header for packet {packet}"
-        );
+        if packet.routing_header.is_empty() {
+            return Err(DroneError::EmptyRoutingHeader(Box::new(packet)));
+        }
 
-        assert!(
-            current_index < packet.routing_header.hops.len(),
-            "hop_index out of bounds: index {current_index} for hops {:?}",
-            packet.routing_header.hops
-        );
+        if current_index >= packet.routing_header.hops.len() {
+            return Err(DroneError::HopIndexOutOfBounds(Box::new(packet)));
+        }
 
-        assert!(
-            current_index != 0,
-            "received packet with hop_index 0, which should be impossible"
-        );
+        if current_index == 0 {
+            return Err(DroneError::HopIndexZero(Box::new(packet)));
+        }
 
         let current_hop = packet.routing_header.hops.get(current_index).unwrap();
         if *current_hop != self.id {
-            self.make_and_send_nack(
+            return self.make_and_send_nack(
                 &packet,
                 current_index,
                 NackType::UnexpectedRecipient(self.id),
             );
-            return;
         }
 
         if packet.routing_header.is_last_hop() {
@@ -50,18 +49,21 @@ impl MyDrone {
                 "Drone is the destination of the packet, sending back {:?}",
                 NackType::DestinationIsDrone
             );
-            self.make_and_send_nack(&packet, current_index, NackType::DestinationIsDrone);
-            return;
+            return self.make_and_send_nack(&packet, current_index, NackType::DestinationIsDrone);
         }
 
         // important because when using send_packet in the following code we want to pass it a
         // packet with the hop_idx alreay pointing to the destination
         packet.routing_header.hop_index += 1;
 
-        self.send_packet(packet);
+        self.send_packet(packet)
     }
 
-    fn process_flood_request(&mut self, flood_request: FloodRequest, session_id: u64) {
+    fn process_flood_request(
+        &mut self,
+        flood_request: FloodRequest,
+        session_id: u64,
+    ) -> Result<(), DroneError> {
         let FloodRequest {
             flood_id,
             initiator_id,
@@ -69,7 +71,10 @@ impl MyDrone {
         } = flood_request;
 
         let Some((received_from, _)) = path_trace.last().copied() else {
-            panic!("flood request has no path trace")
+            return Err(DroneError::MissingPathTrace {
+                flood_id,
+                initiator_id,
+            });
         };
 
         path_trace.push((self.id, NodeType::Drone));
@@ -82,16 +87,36 @@ impl MyDrone {
             .copied()
             .collect();
 
+        let now = self.clock.now();
         let drone_has_no_other_neighbors = neighbors_minus_sender.is_empty();
+        let flood_already_seen = self
+            .known_flood_ids
+            .check_and_insert(flood_id, initiator_id, now);
+        // a token is taken only by the flood requests that would be forwarded
+        let initiator_rate_limited = !flood_already_seen
+            && !drone_has_no_other_neighbors
+            && !self.flood_limiter.try_acquire(initiator_id, now);
+        let (flood_cache_len, flood_cache_evictions) =
+            (self.known_flood_ids.len(), self.known_flood_ids.evictions());
+        self.record(|stats| {
+            stats.flood_cache_len = flood_cache_len;
+            stats.flood_cache_evictions = flood_cache_evictions;
+            if initiator_rate_limited {
+                stats.flood_requests_rate_limited += 1;
+            }
+        });
 
-        if self.known_flood_ids.contains(&(flood_id, initiator_id)) || drone_has_no_other_neighbors
-        {
+        if flood_already_seen || drone_has_no_other_neighbors || initiator_rate_limited {
             if drone_has_no_other_neighbors {
                 log::debug!("Drone has no other neighbors except for the sender, generating flood response...");
+            } else if initiator_rate_limited {
+                log::warn!("initiator {initiator_id} went over the flood rate limit, generating flood response...");
             } else {
                 log::debug!("tuple (flood_id:{},initiator_id:{}) already seen, generating flood response...",flood_id,initiator_id);
             }
 
+            self.record(|stats| stats.flood_requests_answered += 1);
+
             let flood_response = PacketType::FloodResponse(FloodResponse {
                 flood_id,
                 path_trace: path_trace.clone(),
@@ -106,13 +131,14 @@ impl MyDrone {
                 routing_header: SourceRoutingHeader { hop_index: 1, hops },
                 session_id,
             };
-            self.send_packet(flood_response_packet);
+            self.send_packet(flood_response_packet)?;
         } else {
             log::debug!(
                 "found neighbors(except sender): {:?}, forwarding flood request...",
                 neighbors_minus_sender
             );
-            self.known_flood_ids.insert((flood_id, initiator_id));
+
+            self.record(|stats| stats.flood_requests_forwarded += 1);
 
             let flood_request = FloodRequest {
                 flood_id,
@@ -137,8 +163,9 @@ impl MyDrone {
                     session_id,
                 };
 
-                self.send_packet(packet);
+                self.send_packet(packet)?;
             }
         }
+        Ok(())
     }
 }
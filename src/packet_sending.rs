@@ -1,48 +1,47 @@
-use crate::MyDrone;
-use rand::Rng;
+use crate::bandwidth::packet_size;
+use crate::drop_model::roll;
+use crate::{DroneError, MyDrone};
 use wg_2024::controller::DroneEvent;
-use wg_2024::network::SourceRoutingHeader;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
 use wg_2024::packet::{Nack, NackType, Packet, PacketType};
 
 // packet sending section
 impl MyDrone {
     /// takes a packet whose routing header hop index already points to the intended destination
-    /// sends that packet through the `channel` corresponding to the current hop index, panics if there is a `SendError`
-    pub(crate) fn send_packet(&self, mut packet: Packet) {
+    /// sends that packet through the outbound queue of the neighbor corresponding to the current
+    /// hop index
+    /// # Errors
+    /// - `DroneError::PacketSendFailed` if the channel of the neighbor is disconnected
+    /// - `DroneError::HopIndexOutOfBounds` if the hop index does not point to a destination
+    /// - `DroneError::HopIndexZero` if a fragment to a node which is not a neighbor has no
+    ///   previous hop to send the nack to
+    pub(crate) fn send_packet(&mut self, mut packet: Packet) -> Result<(), DroneError> {
         // use hop_idx to get id of destination:
-        let dest = packet
-            .routing_header
-            .current_hop()
-            .expect("next hop not found: the internal function packet_send was passed a packet with no destination(its hop_index, which was incremented by 1 to point to dest, was out of bounds for hops)");
+        let Some(dest) = packet.routing_header.current_hop() else {
+            return Err(DroneError::HopIndexOutOfBounds(Box::new(packet)));
+        };
 
-        if let Some(channel) = self.packet_send.get(&dest) {
+        if let Some(neighbor) = self.packet_send.get(&dest) {
+            let link_pdr = neighbor.pdr;
             // packet drop logic
-            if matches!(packet.pack_type, PacketType::MsgFragment(_))
-                && self.roll_a_dice_and_decide_maybe_drop_packet()
-            {
-                log::info!("Dropping packet due to drone's pdr");
+            if self.roll_a_dice_and_decide_maybe_drop_packet(&packet, link_pdr)? {
+                log::info!("Dropping packet due to drone's drop model");
+                self.record(|stats| {
+                    stats.dropped.count(&packet.pack_type);
+                    if let Some(link) = stats.links.get_mut(&dest) {
+                        link.drops += 1;
+                        link.dropped_wire_bytes += u64::from(packet_size(&packet));
+                    }
+                });
                 packet.routing_header.hop_index -= 1;
-                self.make_and_send_nack(
+                return self.make_and_send_nack(
                     &packet,
                     packet.routing_header.hop_index,
                     NackType::Dropped,
                 );
-                return;
             }
 
-            match channel.send(packet.clone()) {
-                Ok(()) => {
-                    let drone_event = DroneEvent::PacketSent(packet.clone());
-                    self.send_event(&drone_event);
-                    log::info!("Sent to channel of Drone#{} Packet {}", dest, &packet,);
-                }
-                Err(error) => {
-                    panic!(
-                        "Cannot send packet {} into channel {channel:?}. Error: {error:?}",
-                        &packet
-                    );
-                }
-            }
+            self.delay_packet(dest, packet)
         } else {
             match &packet.pack_type {
                 PacketType::MsgFragment(_) => {
@@ -50,34 +49,73 @@ impl MyDrone {
                         "Next hop of header is not a neighbor of drone, creating {:?}",
                         NackType::ErrorInRouting(dest)
                     );
-                    let idx = packet.routing_header.previous_hop().unwrap();
-                    self.make_and_send_nack(&packet, idx as usize, NackType::ErrorInRouting(dest));
+                    // the drone is the hop before the destination
+                    let Some(idx) = packet.routing_header.hop_index.checked_sub(1) else {
+                        return Err(DroneError::HopIndexZero(Box::new(packet)));
+                    };
+                    self.make_and_send_nack(&packet, idx, NackType::ErrorInRouting(dest))
                 }
                 PacketType::FloodRequest(_) => {
+                    // flood requests are only sent by `process_flood_request`, to the keys of
+                    // `packet_send` which it reads just before without changing them
                     unreachable!("Flood request algorithm should never try to send a flood request to a node not in the list of neighbors, as it gets all the neighbors from that list");
                 }
                 _ => {
                     log::info!("Sending packet {packet} to simulation controller to shortcut it");
+                    self.record(|stats| stats.shortcut.count(&packet.pack_type));
                     let event = DroneEvent::ControllerShortcut(packet);
-                    self.send_event(&event);
+                    self.send_event(&event)
                 }
             }
-        };
+        }
+    }
+
+    /// Counts a packet which has just been sent to `dest` and tells the simulation controller
+    /// # Errors
+    /// Returns the errors of `MyDrone::send_event`
+    pub(crate) fn on_packet_sent(
+        &mut self,
+        dest: NodeId,
+        packet: &Packet,
+    ) -> Result<(), DroneError> {
+        self.record(|stats| {
+            stats.forwarded.count(&packet.pack_type);
+            if let Some(link) = stats.links.get_mut(&dest) {
+                link.packets += 1;
+                if let PacketType::MsgFragment(fragment) = &packet.pack_type {
+                    link.bytes += u64::from(fragment.length);
+                }
+            }
+        });
+        let drone_event = DroneEvent::PacketSent(packet.clone());
+        self.send_event(&drone_event)?;
+        log::info!("Sent to channel of Drone#{} Packet {}", dest, packet);
+        Ok(())
     }
 
     /// This method handles the logic of the packet dropping.
-    /// It decides to drop a packet or not and, if it does, it creates and sends the related
-    /// NACK packet
-    fn roll_a_dice_and_decide_maybe_drop_packet(&self) -> bool {
-        let mut rng = rand::rng();
-        let random_number: f32 = rng.random_range(0.0..=1.0);
-        random_number < self.pdr
+    /// It decides to drop a packet or not using the pdr of the link, if it has one, or asking the
+    /// drop model of the drone otherwise, after updating it with the pdr schedule. Only fragments
+    /// can be dropped
+    fn roll_a_dice_and_decide_maybe_drop_packet(
+        &mut self,
+        packet: &Packet,
+        link_pdr: Option<f32>,
+    ) -> Result<bool, DroneError> {
+        Ok(match (&packet.pack_type, link_pdr) {
+            (PacketType::MsgFragment(_), Some(pdr)) => roll(pdr, &mut *self.rng),
+            (PacketType::MsgFragment(fragment), None) => {
+                self.apply_pdr_schedule()?;
+                self.drop_model.should_drop(fragment, &mut *self.rng)
+            }
+            _ => false,
+        })
     }
 
     /// sends an event to the simulation controller
-    /// # Panics
-    /// Panics if `self.controller_send.send()` fails
-    pub fn send_event(&self, event: &DroneEvent) {
+    /// # Errors
+    /// Returns `DroneError::EventSendFailed` if `self.controller_send.send()` fails
+    pub fn send_event(&self, event: &DroneEvent) -> Result<(), DroneError> {
         match self.controller_send.send(event.clone()) {
             Ok(()) => {
                 let (event_type, packet) = match event {
@@ -90,13 +128,9 @@ impl MyDrone {
                     event_type,
                     packet,
                 );
+                Ok(())
             }
-            Err(error) => {
-                panic!(
-                    "Cannot send event {:?} to simulation controller. Error: {error:?}",
-                    &event
-                );
-            }
+            Err(error) => Err(DroneError::EventSendFailed(Box::new(error))),
         }
     }
 
@@ -104,21 +138,21 @@ impl MyDrone {
     /// creates and sends a nack with the given `NackType`, containing the `original_packet` and reversing the
     /// route so that it goes from `original_recipient_idx` to the node that sent `original_packet`
     /// (the one at index 0)
+    /// # Errors
+    /// - `DroneError::HopIndexOutOfBounds` if `original_recipient_idx` is not an index of the hops
+    ///   of `original_packet`
+    /// - the errors of `MyDrone::send_event` and `MyDrone::send_packet`
     pub(crate) fn make_and_send_nack(
-        &self,
+        &mut self,
         original_packet: &Packet,
         original_recipient_idx: usize,
         nack_type: NackType,
-    ) {
-        //expected to be unreachable given the logic of where make and send nack is done
-        assert!(
-            original_packet
-                .routing_header
-                .hops
-                .get(original_recipient_idx)
-                .is_some(),
-            "original recipient index out of bounds, this should be unreachable"
-        );
+    ) -> Result<(), DroneError> {
+        if original_recipient_idx >= original_packet.routing_header.hops.len() {
+            return Err(DroneError::HopIndexOutOfBounds(Box::new(
+                original_packet.clone(),
+            )));
+        }
 
         let fragment_index = match &original_packet.pack_type {
             PacketType::MsgFragment(frag) => frag.fragment_index,
@@ -130,6 +164,7 @@ impl MyDrone {
             fragment_index,
             nack_type,
         };
+        self.record(|stats| stats.nacked.count(nack_type));
 
         let mut new_hops = original_packet.routing_header.hops[0..=original_recipient_idx].to_vec();
         new_hops.reverse();
@@ -145,13 +180,13 @@ impl MyDrone {
         };
 
         if matches!(nack_type, NackType::Dropped) {
-            self.send_event(&DroneEvent::PacketDropped(original_packet.clone()));
+            self.send_event(&DroneEvent::PacketDropped(original_packet.clone()))?;
 
             // another small detail not too clear in the protocol: here we send the
             // original_packet which had already his hop_index increased so its pointing to
             // where we would have sent him if everything went ok, other option is having
             // hop_index-1 as it was when packet arrived.
         }
-        self.send_packet(packet);
+        self.send_packet(packet)
     }
 }
@@ -0,0 +1,237 @@
+use crate::drop_model::check_probability;
+use crate::{DroneError, MyDrone};
+use std::path::Path;
+use std::time::Duration;
+
+/// A pdr that changes over time, measured from the moment the schedule is installed on a drone
+#[derive(Clone, Debug, PartialEq)]
+pub enum PdrSchedule {
+    /// Goes linearly from `from` to `to` in `duration`, then stays at `to`
+    Ramp {
+        from: f32,
+        to: f32,
+        duration: Duration,
+    },
+    /// Stays at `low` for `low_duration`, then at `high` for `high_duration`, and repeats
+    SquareWave {
+        low: f32,
+        high: f32,
+        low_duration: Duration,
+        high_duration: Duration,
+    },
+    /// Each `(start, pdr)` step holds from `start` until the start of the next one, before the
+    /// first step the pdr of the first step is used
+    Piecewise(Vec<(Duration, f32)>),
+}
+
+impl PdrSchedule {
+    /// # Errors
+    /// Returns `DroneError::InvalidPdr` if `from` or `to` are not in range (0.0..=1.0)
+    pub fn ramp(from: f32, to: f32, duration: Duration) -> Result<Self, DroneError> {
+        let schedule = Self::Ramp { from, to, duration };
+        schedule.validate()?;
+        Ok(schedule)
+    }
+
+    /// # Errors
+    /// Returns `DroneError::InvalidPdr` if `low` or `high` are not in range (0.0..=1.0)
+    pub fn square_wave(
+        low: f32,
+        high: f32,
+        low_duration: Duration,
+        high_duration: Duration,
+    ) -> Result<Self, DroneError> {
+        let schedule = Self::SquareWave {
+            low,
+            high,
+            low_duration,
+            high_duration,
+        };
+        schedule.validate()?;
+        Ok(schedule)
+    }
+
+    /// # Errors
+    /// - `DroneError::InvalidPdr` if any pdr is not in range (0.0..=1.0)
+    /// - `DroneError::InvalidPdrSchedule` if there are no steps or they are not sorted by start
+    pub fn piecewise(steps: Vec<(Duration, f32)>) -> Result<Self, DroneError> {
+        let schedule = Self::Piecewise(steps);
+        schedule.validate()?;
+        Ok(schedule)
+    }
+
+    /// Checks that the schedule can only give valid pdr values
+    /// # Errors
+    /// - `DroneError::InvalidPdr` if any pdr is not in range (0.0..=1.0)
+    /// - `DroneError::InvalidPdrSchedule` if a piecewise schedule has no steps or they are not
+    ///   sorted by start
+    pub fn validate(&self) -> Result<(), DroneError> {
+        match self {
+            PdrSchedule::Ramp { from, to, .. } => {
+                check_probability(*from)?;
+                check_probability(*to)?;
+            }
+            PdrSchedule::SquareWave { low, high, .. } => {
+                check_probability(*low)?;
+                check_probability(*high)?;
+            }
+            PdrSchedule::Piecewise(steps) => {
+                if steps.is_empty() {
+                    return Err(DroneError::InvalidPdrSchedule(
+                        "a piecewise schedule needs at least one step".to_string(),
+                    ));
+                }
+                if steps.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+                    return Err(DroneError::InvalidPdrSchedule(
+                        "the steps of a piecewise schedule must be sorted by start time"
+                            .to_string(),
+                    ));
+                }
+                for (_start, pdr) in steps {
+                    check_probability(*pdr)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a piecewise schedule, with one `<seconds> <pdr>` step per line. Empty lines and
+    /// lines starting with `#` are ignored.
+    /// ```text
+    /// # outage between 10 and 15 seconds
+    /// 0    0.05
+    /// 10   1.0
+    /// 15.5 0.05
+    /// ```
+    /// # Errors
+    /// Returns `DroneError::InvalidPdrSchedule` if a line cannot be parsed, or the errors of
+    /// `PdrSchedule::piecewise`
+    pub fn parse(text: &str) -> Result<Self, DroneError> {
+        let mut steps = Vec::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid_line = || {
+                DroneError::InvalidPdrSchedule(format!(
+                    "line {}: expected \"<seconds> <pdr>\", found \"{line}\"",
+                    line_number + 1
+                ))
+            };
+            let mut fields = line.split_whitespace();
+            let (Some(start), Some(pdr), None) = (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid_line());
+            };
+            let start = start
+                .parse::<f64>()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .ok_or_else(invalid_line)?;
+            let pdr = pdr.parse::<f32>().map_err(|_| invalid_line())?;
+            steps.push((start, pdr));
+        }
+        Self::piecewise(steps)
+    }
+
+    /// Reads a piecewise schedule from a file, see `PdrSchedule::parse` for the format
+    /// # Errors
+    /// Returns `DroneError::InvalidPdrSchedule` if the file cannot be read, or the errors of
+    /// `PdrSchedule::parse`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DroneError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|error| {
+            DroneError::InvalidPdrSchedule(format!("cannot read {}: {error}", path.display()))
+        })?;
+        Self::parse(&text)
+    }
+
+    /// Returns the pdr of the schedule after `elapsed` time from its start
+    #[must_use]
+    pub fn pdr_at(&self, elapsed: Duration) -> f32 {
+        match self {
+            PdrSchedule::Ramp { from, to, duration } => {
+                if elapsed >= *duration {
+                    return *to;
+                }
+                let progress = elapsed.as_secs_f32() / duration.as_secs_f32();
+                from + (to - from) * progress
+            }
+            PdrSchedule::SquareWave {
+                low,
+                high,
+                low_duration,
+                high_duration,
+            } => {
+                let period = (*low_duration + *high_duration).as_nanos();
+                if period == 0 || elapsed.as_nanos() % period < low_duration.as_nanos() {
+                    *low
+                } else {
+                    *high
+                }
+            }
+            PdrSchedule::Piecewise(steps) => steps
+                .iter()
+                .take_while(|(start, _pdr)| *start <= elapsed)
+                .last()
+                .or(steps.first())
+                .map_or(0.0, |(_start, pdr)| *pdr),
+        }
+    }
+}
+
+impl MyDrone {
+    /// Installs a pdr schedule starting now, or removes the current one with `None`. The schedule
+    /// drives the pdr of the drone through `set_pdr`, so it replaces any other drop model.
+    /// # Errors
+    /// Returns the errors of `PdrSchedule::validate`
+    pub(crate) fn set_pdr_schedule(
+        &mut self,
+        pdr_schedule: Option<PdrSchedule>,
+    ) -> Result<(), DroneError> {
+        if let Some(schedule) = &pdr_schedule {
+            schedule.validate()?;
+            self.set_pdr(schedule.pdr_at(Duration::ZERO))?;
+        }
+        log::info!("pdr schedule set to {pdr_schedule:?}");
+        let now = self.clock.now();
+        self.pdr_schedule = pdr_schedule.map(|schedule| (schedule, now));
+        Ok(())
+    }
+
+    /// Installs a pdr schedule which starts now, until a `DroneCommand::SetPacketDropRate` removes
+    /// it. The schedule drives the pdr of the drone, so it cannot be combined with a custom
+    /// drop model.
+    /// # Errors
+    /// - `DroneError::InvalidPdrSchedule` if a drop model was given with `MyDrone::with_drop_model`
+    /// - the errors of `PdrSchedule::validate`
+    pub fn with_pdr_schedule(mut self, pdr_schedule: PdrSchedule) -> Result<Self, DroneError> {
+        if self.custom_drop_model {
+            return Err(DroneError::InvalidPdrSchedule(
+                "a pdr schedule cannot be used with a custom drop model".to_string(),
+            ));
+        }
+        self.set_pdr_schedule(Some(pdr_schedule))?;
+        Ok(self)
+    }
+
+    /// Evaluates the pdr schedule, if there is one, and updates the pdr of the drone when the
+    /// schedule gives a different value
+    /// # Errors
+    /// Returns `DroneError::InvalidPdr` if the schedule gives an invalid pdr
+    pub(crate) fn apply_pdr_schedule(&mut self) -> Result<(), DroneError> {
+        let Some((schedule, start)) = &self.pdr_schedule else {
+            return Ok(());
+        };
+        let pdr = schedule.pdr_at(self.clock.now().saturating_duration_since(*start));
+        if (pdr - self.pdr).abs() > f32::EPSILON {
+            log::debug!(
+                "effective pdr changed from {} to {pdr} following the pdr schedule",
+                self.pdr
+            );
+            self.replace_pdr(pdr)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use rand::RngCore;
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+
+/// The random number generator owned by a drone, used to decide whether to drop packets.
+///
+/// It wraps any `RngCore` so that users can plug their own generator, and it implements `Debug`
+/// without printing the internal state of the generator.
+pub(crate) struct DroneRng(Box<dyn RngCore + Send>);
+
+impl DroneRng {
+    pub(crate) fn new(rng: impl RngCore + Send + 'static) -> Self {
+        Self(Box::new(rng))
+    }
+}
+
+impl Debug for DroneRng {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DroneRng {{ .. }}")
+    }
+}
+
+impl Deref for DroneRng {
+    type Target = dyn RngCore + Send;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl DerefMut for DroneRng {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.as_mut()
+    }
+}
@@ -0,0 +1,669 @@
+//! Scripted experiments: a TOML file describing a network, the actions to take on it at given
+//! times and the packets the clients and the servers are expected to receive, run on `MyDrone`s
+//! and a `SimulationController` into a pass/fail report.
+//!
+//! ```toml
+//! topology = "topology.toml" # or [[drone]], [[client]] and [[server]] tables as in the topology
+//! duration = 5.0             # seconds, 1 second after the last step by default
+//! seed = 42                  # optional, makes the drops of the drones reproducible
+//!
+//! [[step]]
+//! at = 0.0
+//! action = "flood"
+//! from = 10
+//!
+//! [[step]]
+//! at = 2.0
+//! action = "crash"
+//! drone = 3
+//!
+//! [[expect]]
+//! node = 10
+//! receives = "flood_response"
+//! containing = 7
+//! within = 1.0
+//! ```
+//!
+//! The actions are `flood` and `send` (a fragment along `hops`) from a client or a server, and
+//! `crash`, `set_pdr` (`drone`, `pdr`), `add_link` and `remove_link` (`a`, `b`) through the
+//! simulation controller.
+
+use crate::network::{self, HostHandle, NetworkError};
+use crate::topology;
+use crate::{ControllerError, DroneExecutor, DroneStats, SimulationController};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use wg_2024::config::{self, Config};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{
+    FloodRequest, FloodResponse, Fragment, NodeType, Packet, PacketType, FRAGMENT_DSIZE,
+};
+
+/// How long the runner waits for an event of the drones before looking at the hosts again
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+/// How long a scenario goes on after its last step when it has no duration
+const DEFAULT_TAIL: Duration = Duration::from_secs(1);
+
+/// Everything that can go wrong while running a scenario
+#[derive(Debug)]
+pub enum ScenarioError {
+    /// The scenario file cannot be read
+    Io(io::Error),
+    /// The scenario file is not valid TOML or does not follow the format of the scenarios
+    Parse(toml::de::Error),
+    /// A time of the scenario is negative or not finite
+    InvalidTime(f64),
+    /// The network of the scenario cannot be set up
+    Network(NetworkError),
+    /// A worker thread of the drones cannot be spawned
+    Spawn(io::Error),
+    /// The simulation controller refused a step
+    Controller(ControllerError),
+    /// A step sends a packet from a node which is not a client or a server
+    UnknownHost(NodeId),
+    /// A step sends a packet along hops which do not start with the host and one of its drones
+    InvalidRoute(Vec<NodeId>),
+}
+
+impl Display for ScenarioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScenarioError::Io(error) => write!(f, "Cannot read the scenario file: {error}"),
+            ScenarioError::Parse(error) => write!(f, "Cannot parse the scenario file: {error}"),
+            ScenarioError::InvalidTime(time) => {
+                write!(f, "{time} is not a valid time in seconds")
+            }
+            ScenarioError::Network(error) => write!(f, "{error}"),
+            ScenarioError::Spawn(error) => write!(f, "Cannot spawn the drones: {error}"),
+            ScenarioError::Controller(error) => write!(f, "{error}"),
+            ScenarioError::UnknownHost(id) => write!(f, "There is no client or server {id}"),
+            ScenarioError::InvalidRoute(hops) => write!(
+                f,
+                "The route {hops:?} does not start with a host and one of its drones"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+impl From<NetworkError> for ScenarioError {
+    fn from(error: NetworkError) -> Self {
+        ScenarioError::Network(error)
+    }
+}
+
+impl From<ControllerError> for ScenarioError {
+    fn from(error: ControllerError) -> Self {
+        ScenarioError::Controller(error)
+    }
+}
+
+/// Something done to the network during a scenario
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// A client or a server starts a flood
+    Flood {
+        from: NodeId,
+    },
+    /// A client or a server sends a fragment along `hops`, which start with it
+    Send {
+        from: NodeId,
+        hops: Vec<NodeId>,
+    },
+    Crash {
+        drone: NodeId,
+    },
+    SetPdr {
+        drone: NodeId,
+        pdr: f32,
+    },
+    AddLink {
+        a: NodeId,
+        b: NodeId,
+    },
+    RemoveLink {
+        a: NodeId,
+        b: NodeId,
+    },
+}
+
+/// An action and when it is done, in seconds from the start of the scenario
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Step {
+    #[serde(default)]
+    pub at: f64,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+/// The kinds of packets a scenario can expect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketKind {
+    Fragment,
+    Ack,
+    Nack,
+    FloodRequest,
+    FloodResponse,
+}
+
+impl PacketKind {
+    fn of(pack_type: &PacketType) -> Self {
+        match pack_type {
+            PacketType::MsgFragment(_) => PacketKind::Fragment,
+            PacketType::Ack(_) => PacketKind::Ack,
+            PacketType::Nack(_) => PacketKind::Nack,
+            PacketType::FloodRequest(_) => PacketKind::FloodRequest,
+            PacketType::FloodResponse(_) => PacketKind::FloodResponse,
+        }
+    }
+}
+
+impl Display for PacketKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PacketKind::Fragment => "fragment",
+            PacketKind::Ack => "ack",
+            PacketKind::Nack => "nack",
+            PacketKind::FloodRequest => "flood_request",
+            PacketKind::FloodResponse => "flood_response",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A packet a client or a server should receive during the scenario
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Expectation {
+    pub node: NodeId,
+    pub receives: PacketKind,
+    /// A node which must be in the path trace of a flood packet, or in the hops of the others
+    pub containing: Option<NodeId>,
+    /// Seconds from the start of the scenario by which the packet must arrive, the end of the
+    /// scenario by default
+    pub within: Option<f64>,
+}
+
+impl Expectation {
+    fn matches(&self, packet: &Packet) -> bool {
+        if PacketKind::of(&packet.pack_type) != self.receives {
+            return false;
+        }
+        let Some(node) = self.containing else {
+            return true;
+        };
+        match &packet.pack_type {
+            PacketType::FloodRequest(FloodRequest { path_trace, .. })
+            | PacketType::FloodResponse(FloodResponse { path_trace, .. }) => {
+                path_trace.iter().any(|(id, _)| *id == node)
+            }
+            _ => packet.routing_header.hops.contains(&node),
+        }
+    }
+}
+
+impl Display for Expectation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "node {} receives a {}", self.node, self.receives)?;
+        if let Some(node) = self.containing {
+            write!(f, " containing {node}")?;
+        }
+        if let Some(within) = self.within {
+            write!(f, " within {within}s")?;
+        }
+        Ok(())
+    }
+}
+
+/// The file format of a scenario
+#[derive(Deserialize)]
+struct ScenarioFile {
+    topology: Option<PathBuf>,
+    #[serde(default)]
+    drone: Vec<config::Drone>,
+    #[serde(default)]
+    client: Vec<config::Client>,
+    #[serde(default)]
+    server: Vec<config::Server>,
+    duration: Option<f64>,
+    seed: Option<u64>,
+    #[serde(default)]
+    step: Vec<Step>,
+    #[serde(default)]
+    expect: Vec<Expectation>,
+}
+
+/// A scripted experiment, see the module documentation for its file format
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub config: Config,
+    pub duration: Duration,
+    /// Seed of the drone with id 0, the others get `seed + id`, random if `None`
+    pub seed: Option<u64>,
+    pub steps: Vec<Step>,
+    pub expectations: Vec<Expectation>,
+}
+
+impl Scenario {
+    /// Reads the scenario file at `path`, with its topology file relative to it
+    /// # Errors
+    /// `ScenarioError::Io` if a file cannot be read, `ScenarioError::Parse` if it is malformed,
+    /// `ScenarioError::InvalidTime` if a time is not a valid number of seconds,
+    /// `ScenarioError::Controller` if a step adds a link to a missing node or from a node to
+    /// itself, or removes a link which does not exist at that time
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(ScenarioError::Io)?;
+        Self::parse_in(&text, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Parses a scenario, with its topology file relative to the current directory
+    /// # Errors
+    /// Same as `Scenario::load`
+    pub fn parse(text: &str) -> Result<Self, ScenarioError> {
+        Self::parse_in(text, Path::new(""))
+    }
+
+    fn parse_in(text: &str, dir: &Path) -> Result<Self, ScenarioError> {
+        let file: ScenarioFile = toml::from_str(text).map_err(ScenarioError::Parse)?;
+        let config = match file.topology {
+            Some(topology) => match network::load(dir.join(topology)) {
+                Ok(config) => config,
+                Err(NetworkError::Io(error)) => return Err(ScenarioError::Io(error)),
+                Err(NetworkError::Parse(error)) => return Err(ScenarioError::Parse(error)),
+                Err(error) => return Err(error.into()),
+            },
+            None => Config {
+                drone: file.drone,
+                client: file.client,
+                server: file.server,
+            },
+        };
+        let mut steps = file.step;
+        for step in &steps {
+            seconds(step.at)?;
+        }
+        for within in file
+            .expect
+            .iter()
+            .filter_map(|expectation| expectation.within)
+        {
+            seconds(within)?;
+        }
+        // the steps at the same time are done in the order of the file
+        steps.sort_by(|a, b| a.at.total_cmp(&b.at));
+        check_links(&config, &steps)?;
+        let duration = match file.duration {
+            Some(duration) => seconds(duration)?,
+            None => steps.last().map_or(Duration::ZERO, |step| {
+                Duration::from_secs_f64(step.at) + DEFAULT_TAIL
+            }),
+        };
+        Ok(Self {
+            config,
+            duration,
+            seed: file.seed,
+            steps,
+            expectations: file.expect,
+        })
+    }
+
+    /// Runs the scenario in real time: spawns the drones on a `DroneExecutor`, does the steps at
+    /// their time with stand-in clients and servers, which answer flood requests with flood
+    /// responses and fragments with acks, and finally crashes every drone and checks what the
+    /// hosts have received
+    /// # Errors
+    /// The network cannot be set up or a step cannot be done, the link steps are checked again
+    /// since the topology or the steps may have changed since the scenario was loaded
+    pub fn run(&self) -> Result<ScenarioReport, ScenarioError> {
+        check_links(&self.config, &self.steps)?;
+        let (mut network, drones) =
+            network::init_with(&self.config, |drone, builder| match self.seed {
+                Some(seed) => builder.seed(seed.wrapping_add(u64::from(drone.id))),
+                None => builder,
+            })?;
+        let stats: Vec<_> = drones
+            .iter()
+            .map(|drone| (drone.id, drone.stats_handle()))
+            .collect();
+        let workers = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+        let executor = DroneExecutor::spawn(drones, workers).map_err(ScenarioError::Spawn)?;
+        let mut controller = SimulationController::new(&mut network, &self.config);
+        let mut hosts: HashMap<NodeId, Host> = network
+            .clients
+            .into_iter()
+            .map(|(id, handle)| (id, Host::new(id, NodeType::Client, handle)))
+            .chain(
+                network
+                    .servers
+                    .into_iter()
+                    .map(|(id, handle)| (id, Host::new(id, NodeType::Server, handle))),
+            )
+            .collect();
+
+        let start = Instant::now();
+        let result = self.play(&mut controller, &mut hosts, start);
+
+        // tear the network down whatever happened, the drones stop once nobody can reach them
+        for drone in controller.drones() {
+            if let Err(error) = controller.crash(drone) {
+                log::warn!("cannot crash drone {drone}: {error}");
+            }
+        }
+        for host in hosts.values_mut() {
+            host.handle.neighbors.clear();
+        }
+        let mut panicked: Vec<NodeId> = executor
+            .join()
+            .into_iter()
+            .filter(|(_, exit)| exit.is_err())
+            .map(|(id, _)| id)
+            .collect();
+        panicked.sort_unstable();
+        result?;
+
+        let results = self
+            .expectations
+            .iter()
+            .map(|expectation| {
+                let deadline = expectation.within.map(Duration::from_secs_f64);
+                let received = hosts.get(&expectation.node).and_then(|host| {
+                    host.received
+                        .iter()
+                        .filter(|(at, _)| deadline.is_none_or(|deadline| *at <= deadline))
+                        .find(|(_, packet)| expectation.matches(packet))
+                        .map(|(at, _)| *at)
+                });
+                ExpectationResult {
+                    expectation: expectation.clone(),
+                    received,
+                }
+            })
+            .collect();
+        let stats = stats
+            .into_iter()
+            .map(|(id, handle)| (id, handle.snapshot()))
+            .collect();
+        Ok(ScenarioReport {
+            results,
+            panicked,
+            stats,
+        })
+    }
+
+    fn play(
+        &self,
+        controller: &mut SimulationController,
+        hosts: &mut HashMap<NodeId, Host>,
+        start: Instant,
+    ) -> Result<(), ScenarioError> {
+        let mut steps = self.steps.iter().peekable();
+        let mut flood_id = 0;
+        loop {
+            let elapsed = start.elapsed();
+            while let Some(step) = steps.next_if(|step| Duration::from_secs_f64(step.at) <= elapsed)
+            {
+                log::info!("{:.3}s: {:?}", elapsed.as_secs_f64(), step.action);
+                match &step.action {
+                    Action::Flood { from } => {
+                        flood_id += 1;
+                        host(hosts, *from)?.flood(flood_id);
+                    }
+                    Action::Send { from, hops } => host(hosts, *from)?.send(hops)?,
+                    Action::Crash { drone } => {
+                        controller.crash(*drone)?;
+                        // the drone stops only once nobody can send to it
+                        for host in hosts.values_mut() {
+                            host.handle.neighbors.remove(drone);
+                        }
+                    }
+                    Action::SetPdr { drone, pdr } => controller.set_pdr(*drone, *pdr)?,
+                    Action::AddLink { a, b } => {
+                        controller.add_link(*a, *b)?;
+                        link_host(hosts, controller, *a, *b);
+                        link_host(hosts, controller, *b, *a);
+                    }
+                    Action::RemoveLink { a, b } => {
+                        controller.remove_link(*a, *b)?;
+                        for (host, neighbor) in [(a, b), (b, a)] {
+                            if let Some(host) = hosts.get_mut(host) {
+                                host.handle.neighbors.remove(neighbor);
+                            }
+                        }
+                    }
+                }
+            }
+            for host in hosts.values_mut() {
+                host.receive(elapsed);
+            }
+            if elapsed >= self.duration {
+                return Ok(());
+            }
+            controller.process_events_timeout(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Follows the links of the network through the steps, so that a mistake in a link step is
+/// reported before the drones run instead of making them fail
+fn check_links(config: &Config, steps: &[Step]) -> Result<(), ScenarioError> {
+    let link = |a: NodeId, b: NodeId| (a.min(b), a.max(b));
+    let mut nodes: HashSet<NodeId> = HashSet::new();
+    let mut links: HashSet<(NodeId, NodeId)> = HashSet::new();
+    for (node, neighbors) in topology::links(config) {
+        nodes.insert(node);
+        links.extend(neighbors.iter().map(|neighbor| link(node, *neighbor)));
+    }
+    for step in steps {
+        match step.action {
+            Action::Crash { drone } => {
+                nodes.remove(&drone);
+                links.retain(|(a, b)| *a != drone && *b != drone);
+            }
+            Action::AddLink { a, b } => {
+                if a == b {
+                    return Err(ControllerError::SelfLink(a).into());
+                }
+                if let Some(node) = [a, b].into_iter().find(|node| !nodes.contains(node)) {
+                    return Err(ControllerError::UnknownNode(node).into());
+                }
+                links.insert(link(a, b));
+            }
+            Action::RemoveLink { a, b } if !links.remove(&link(a, b)) => {
+                return Err(ControllerError::UnknownLink(a, b).into());
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn host(hosts: &mut HashMap<NodeId, Host>, id: NodeId) -> Result<&mut Host, ScenarioError> {
+    hosts.get_mut(&id).ok_or(ScenarioError::UnknownHost(id))
+}
+
+/// Gives `host`, if it is a host, a sender to the drone it has just been connected to
+fn link_host(
+    hosts: &mut HashMap<NodeId, Host>,
+    controller: &SimulationController,
+    host: NodeId,
+    drone: NodeId,
+) {
+    if let (Some(host), Some(sender)) = (hosts.get_mut(&host), controller.packet_sender(drone)) {
+        host.handle.neighbors.insert(drone, sender);
+    }
+}
+
+/// A stand-in client or server
+struct Host {
+    id: NodeId,
+    node_type: NodeType,
+    handle: HostHandle,
+    /// Every packet received, with the time since the start of the scenario
+    received: Vec<(Duration, Packet)>,
+    session_id: u64,
+}
+
+impl Host {
+    fn new(id: NodeId, node_type: NodeType, handle: HostHandle) -> Self {
+        Self {
+            id,
+            node_type,
+            handle,
+            received: Vec::new(),
+            session_id: 0,
+        }
+    }
+
+    fn flood(&mut self, flood_id: u64) {
+        self.session_id += 1;
+        let request = FloodRequest::initialize(flood_id, self.id, self.node_type);
+        let packet =
+            Packet::new_flood_request(SourceRoutingHeader::empty_route(), self.session_id, request);
+        for (drone, sender) in &self.handle.neighbors {
+            if sender.send(packet.clone()).is_err() {
+                log::warn!("host {} cannot send its flood request to {drone}", self.id);
+            }
+        }
+    }
+
+    fn send(&mut self, hops: &[NodeId]) -> Result<(), ScenarioError> {
+        if hops.first() != Some(&self.id) || hops.len() < 2 {
+            return Err(ScenarioError::InvalidRoute(hops.to_vec()));
+        }
+        self.session_id += 1;
+        let fragment = Fragment {
+            fragment_index: 0,
+            total_n_fragments: 1,
+            length: 0,
+            data: [0; FRAGMENT_DSIZE],
+        };
+        let packet = Packet::new_fragment(
+            SourceRoutingHeader::with_first_hop(hops.to_vec()),
+            self.session_id,
+            fragment,
+        );
+        self.forward(packet)
+            .map_err(|()| ScenarioError::InvalidRoute(hops.to_vec()))
+    }
+
+    /// Receives everything pending, answering flood requests and fragments
+    fn receive(&mut self, elapsed: Duration) {
+        while let Ok(packet) = self.handle.packet_recv.try_recv() {
+            log::info!("host {} received {packet}", self.id);
+            let answer = match &packet.pack_type {
+                PacketType::FloodRequest(request) => {
+                    let mut path_trace = request.path_trace.clone();
+                    path_trace.push((self.id, self.node_type));
+                    let hops = path_trace.iter().rev().map(|(id, _)| *id).collect();
+                    let response = FloodResponse {
+                        flood_id: request.flood_id,
+                        path_trace,
+                    };
+                    Some(Packet::new_flood_response(
+                        SourceRoutingHeader::with_first_hop(hops),
+                        packet.session_id,
+                        response,
+                    ))
+                }
+                PacketType::MsgFragment(fragment) => {
+                    let mut hops = packet.routing_header.hops.clone();
+                    hops.truncate(packet.routing_header.hop_index + 1);
+                    hops.reverse();
+                    Some(Packet::new_ack(
+                        SourceRoutingHeader::with_first_hop(hops),
+                        packet.session_id,
+                        fragment.fragment_index,
+                    ))
+                }
+                _ => None,
+            };
+            self.received.push((elapsed, packet));
+            if let Some(answer) = answer {
+                if self.forward(answer).is_err() {
+                    log::warn!("host {} cannot answer, its drone is gone", self.id);
+                }
+            }
+        }
+    }
+
+    /// Sends a packet whose `hop_index` points to one of the drones of the host
+    fn forward(&self, packet: Packet) -> Result<(), ()> {
+        let next_hop = packet.routing_header.current_hop().ok_or(())?;
+        let sender = self.handle.neighbors.get(&next_hop).ok_or(())?;
+        sender.send(packet).map_err(|_| ())
+    }
+}
+
+/// Whether an expectation of a scenario was met
+#[derive(Debug, Clone)]
+pub struct ExpectationResult {
+    pub expectation: Expectation,
+    /// When the first matching packet was received, `None` if the expectation failed
+    pub received: Option<Duration>,
+}
+
+impl ExpectationResult {
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.received.is_some()
+    }
+}
+
+impl Display for ExpectationResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.received {
+            Some(at) => write!(
+                f,
+                "PASS {} (after {:.3}s)",
+                self.expectation,
+                at.as_secs_f64()
+            ),
+            None => write!(f, "FAIL {}", self.expectation),
+        }
+    }
+}
+
+/// The outcome of a scenario
+#[derive(Debug, Clone)]
+pub struct ScenarioReport {
+    /// One result per expectation, in the order of the scenario
+    pub results: Vec<ExpectationResult>,
+    /// The drones which panicked during the scenario
+    pub panicked: Vec<NodeId>,
+    /// The statistics of every drone at the end of the scenario
+    pub stats: Vec<(NodeId, DroneStats)>,
+}
+
+impl ScenarioReport {
+    /// `true` if every expectation was met and no drone panicked
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.panicked.is_empty() && self.results.iter().all(ExpectationResult::passed)
+    }
+}
+
+impl Display for ScenarioReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for result in &self.results {
+            writeln!(f, "{result}")?;
+        }
+        for drone in &self.panicked {
+            writeln!(f, "FAIL drone {drone} panicked")?;
+        }
+        let passed = self.results.iter().filter(|result| result.passed()).count();
+        write!(f, "{passed}/{} expectations met", self.results.len())
+    }
+}
+
+fn seconds(time: f64) -> Result<Duration, ScenarioError> {
+    Duration::try_from_secs_f64(time).map_err(|_| ScenarioError::InvalidTime(time))
+}
@@ -0,0 +1,193 @@
+use crate::bandwidth::{packet_size, MAX_PACKET_SIZE};
+use crate::MyDrone;
+use std::collections::VecDeque;
+use std::num::NonZeroU32;
+use wg_2024::packet::{Packet, PacketType};
+
+/// Class of a packet in the outbound queues, by `PacketType` variant
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TrafficClass {
+    /// `Ack`, `Nack` and `FloodResponse`: small packets whose latency matters
+    Control,
+    /// `FloodRequest`
+    Discovery,
+    /// `MsgFragment`, the bulk of the traffic
+    Data,
+}
+
+impl TrafficClass {
+    /// All the classes, from the highest priority to the lowest
+    pub const ALL: [TrafficClass; 3] = [
+        TrafficClass::Control,
+        TrafficClass::Discovery,
+        TrafficClass::Data,
+    ];
+
+    #[must_use]
+    pub fn of(pack_type: &PacketType) -> Self {
+        match pack_type {
+            PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_) => {
+                TrafficClass::Control
+            }
+            PacketType::FloodRequest(_) => TrafficClass::Discovery,
+            PacketType::MsgFragment(_) => TrafficClass::Data,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            TrafficClass::Control => 0,
+            TrafficClass::Discovery => 1,
+            TrafficClass::Data => 2,
+        }
+    }
+}
+
+/// Share of the link given to each class by `Scheduler::WeightedFair`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClassWeights {
+    pub control: NonZeroU32,
+    pub discovery: NonZeroU32,
+    pub data: NonZeroU32,
+}
+
+impl ClassWeights {
+    #[must_use]
+    pub fn get(&self, class: TrafficClass) -> NonZeroU32 {
+        match class {
+            TrafficClass::Control => self.control,
+            TrafficClass::Discovery => self.discovery,
+            TrafficClass::Data => self.data,
+        }
+    }
+}
+
+impl Default for ClassWeights {
+    /// 4 to control packets, 2 to flood requests and 1 to fragments
+    fn default() -> Self {
+        const fn weight(weight: u32) -> NonZeroU32 {
+            match NonZeroU32::new(weight) {
+                Some(weight) => weight,
+                None => unreachable!(),
+            }
+        }
+        Self {
+            control: weight(4),
+            discovery: weight(2),
+            data: weight(1),
+        }
+    }
+}
+
+/// Order in which the packets waiting in the outbound queue of a neighbor are sent
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scheduler {
+    /// Sends the packets of a class only when the classes before it in `TrafficClass::ALL` have
+    /// nothing waiting, so control packets always leave first
+    #[default]
+    StrictPriority,
+    /// Deficit round robin over the classes: while all of them have packets waiting, each class
+    /// gets a share of the bytes sent proportional to its weight, so fragments are never starved
+    WeightedFair(ClassWeights),
+}
+
+/// Outbound queue of a neighbor, with a FIFO queue for every `TrafficClass`
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClassQueues {
+    queues: [VecDeque<Packet>; 3],
+    /// bytes each class can still send in its turn of the weighted fair scheduler
+    deficits: [u64; 3],
+    /// class whose turn it is in the weighted fair scheduler
+    turn: usize,
+}
+
+impl ClassQueues {
+    pub(crate) fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.queues.iter().all(VecDeque::is_empty)
+    }
+
+    pub(crate) fn push_back(&mut self, packet: Packet) {
+        self.queues[TrafficClass::of(&packet.pack_type).index()].push_back(packet);
+    }
+
+    /// Removes the oldest packet of the lowest priority class with packets waiting
+    pub(crate) fn pop_lowest_priority(&mut self) -> Option<Packet> {
+        self.queues.iter_mut().rev().find_map(VecDeque::pop_front)
+    }
+
+    /// Returns the packet the scheduler sends next, without taking a turn of the scheduler
+    pub(crate) fn front(&self, scheduler: Scheduler) -> Option<&Packet> {
+        let (class, _deficits) = self.next_class(scheduler)?;
+        self.queues[class].front()
+    }
+
+    /// Removes the packet the scheduler sends next, the weighted fair scheduler moves on to its
+    /// class and charges it for the packet
+    pub(crate) fn pop_front(&mut self, scheduler: Scheduler) -> Option<Packet> {
+        let (class, deficits) = self.next_class(scheduler)?;
+        let packet = self.queues[class].pop_front()?;
+        if matches!(scheduler, Scheduler::WeightedFair(_)) {
+            self.turn = class;
+            self.deficits = deficits;
+            self.deficits[class] -= u64::from(packet_size(&packet));
+        }
+        Some(packet)
+    }
+
+    /// Puts back a packet returned by `ClassQueues::pop_front` which could not be sent, so that it
+    /// is the next one again
+    pub(crate) fn push_front(&mut self, packet: Packet, scheduler: Scheduler) {
+        let class = TrafficClass::of(&packet.pack_type).index();
+        if matches!(scheduler, Scheduler::WeightedFair(_)) {
+            self.deficits[class] += u64::from(packet_size(&packet));
+        }
+        self.queues[class].push_front(packet);
+    }
+
+    /// Index of the class of the next packet to send, `None` if the queues are empty, with the
+    /// deficits of the classes once the weighted fair scheduler reaches it. The weighted fair
+    /// scheduler stays on the same class until it sends a packet.
+    fn next_class(&self, scheduler: Scheduler) -> Option<(usize, [u64; 3])> {
+        if self.is_empty() {
+            return None;
+        }
+        let Scheduler::WeightedFair(weights) = scheduler else {
+            let class = self.queues.iter().position(|queue| !queue.is_empty())?;
+            return Some((class, self.deficits));
+        };
+        let mut turn = self.turn;
+        let mut deficits = self.deficits;
+        loop {
+            match self.queues[turn].front() {
+                Some(packet) if deficits[turn] >= u64::from(packet_size(packet)) => {
+                    return Some((turn, deficits));
+                }
+                front => {
+                    // an idle class does not save up its turns
+                    if front.is_none() {
+                        deficits[turn] = 0;
+                    }
+                    turn = (turn + 1) % self.queues.len();
+                    // the quantum is at least the biggest packet, so every class with packets
+                    // waiting sends at least one of them in its turn
+                    let weight = weights.get(TrafficClass::ALL[turn]).get();
+                    deficits[turn] += u64::from(weight) * u64::from(MAX_PACKET_SIZE);
+                }
+            }
+        }
+    }
+}
+
+impl MyDrone {
+    /// Sets the order in which the packets waiting in the outbound queues are sent
+    #[must_use]
+    pub fn with_scheduler(mut self, scheduler: Scheduler) -> Self {
+        log::info!("outbound scheduler set to {scheduler:?}");
+        self.scheduler = scheduler;
+        self
+    }
+}
@@ -0,0 +1,321 @@
+use crate::network::{DroneHandle, Network};
+use crossbeam_channel::{Receiver, Select, Sender, TryRecvError};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+use wg_2024::config::Config;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Everything the `SimulationController` can refuse to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControllerError {
+    /// There is no drone with this id, or it has crashed
+    UnknownDrone(NodeId),
+    /// There is no node with this id, or it is a crashed drone
+    UnknownNode(NodeId),
+    /// Tried to connect a node to itself
+    SelfLink(NodeId),
+    /// Tried to remove a link which does not exist
+    UnknownLink(NodeId, NodeId),
+    /// Tried to set a pdr outside of the range `0.0..=1.0`
+    InvalidPdr(f32),
+    /// The drone stopped receiving commands
+    DroneDisconnected(NodeId),
+}
+
+impl Display for ControllerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControllerError::UnknownDrone(id) => write!(f, "There is no running drone {id}"),
+            ControllerError::UnknownNode(id) => write!(f, "There is no node {id}"),
+            ControllerError::SelfLink(id) => write!(f, "Node {id} cannot be linked to itself"),
+            ControllerError::UnknownLink(a, b) => write!(f, "There is no link {a}-{b}"),
+            ControllerError::InvalidPdr(pdr) => write!(
+                f,
+                "Tried to set an invalid pdr value of {pdr}, which is not in range (0.0..=1.0)"
+            ),
+            ControllerError::DroneDisconnected(id) => {
+                write!(f, "Drone {id} does not receive commands anymore")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ControllerError {}
+
+/// A headless simulation controller, to run a network of drones without the one of another team.
+///
+/// It talks to every drone through the channels of its `DroneHandle`, keeps track of the links
+/// of the network, delivers the packets the drones shortcut to it and logs the other events.
+/// Clients and servers have no command channel, so when a link to one of them changes only the
+/// drone side of the link is told.
+#[derive(Debug)]
+pub struct SimulationController {
+    drones: HashMap<NodeId, DroneHandle>,
+    /// Drones which have been told to crash, whose events are still processed until they stop
+    crashed: HashMap<NodeId, CrashedDrone>,
+    /// Senders to the packet channel of every node which is not crashed
+    packet_send: HashMap<NodeId, Sender<Packet>>,
+    links: HashMap<NodeId, HashSet<NodeId>>,
+}
+
+/// The channels of a crashing drone, without its packet sender, which would keep it running
+#[derive(Debug)]
+struct CrashedDrone {
+    /// A crashing drone still processes commands, dropping the sender would make it fail
+    _command_send: Sender<DroneCommand>,
+    event_recv: Receiver<DroneEvent>,
+}
+
+impl SimulationController {
+    /// Takes control of the drones of `network`, whose links are the ones of `config`, moving
+    /// their handles out of it. The clients and the servers are left to you.
+    #[must_use]
+    pub fn new(network: &mut Network, config: &Config) -> Self {
+        let drones = std::mem::take(&mut network.drones);
+        let packet_send = drones
+            .iter()
+            .map(|(id, drone)| (*id, drone.packet_send.clone()))
+            .chain(
+                network
+                    .clients
+                    .iter()
+                    .chain(&network.servers)
+                    .map(|(id, host)| (*id, host.packet_send.clone())),
+            )
+            .collect();
+        let mut links: HashMap<NodeId, HashSet<NodeId>> = HashMap::new();
+        for (node, neighbors) in crate::topology::links(config) {
+            for neighbor in neighbors {
+                links.entry(node).or_default().insert(*neighbor);
+                links.entry(*neighbor).or_default().insert(node);
+            }
+        }
+        Self {
+            drones,
+            crashed: HashMap::new(),
+            packet_send,
+            links,
+        }
+    }
+
+    /// The drones which have not crashed, sorted by id
+    #[must_use]
+    pub fn drones(&self) -> Vec<NodeId> {
+        let mut drones: Vec<NodeId> = self.drones.keys().copied().collect();
+        drones.sort_unstable();
+        drones
+    }
+
+    /// A sender to the packet channel of a node which has not crashed
+    #[must_use]
+    pub fn packet_sender(&self, node: NodeId) -> Option<Sender<Packet>> {
+        self.packet_send.get(&node).cloned()
+    }
+
+    /// The nodes currently connected to `node`
+    #[must_use]
+    pub fn neighbors(&self, node: NodeId) -> Vec<NodeId> {
+        let mut neighbors: Vec<NodeId> = self
+            .links
+            .get(&node)
+            .map(|neighbors| neighbors.iter().copied().collect())
+            .unwrap_or_default();
+        neighbors.sort_unstable();
+        neighbors
+    }
+
+    /// Crashes a drone: sends it `DroneCommand::Crash` and removes it from its neighbors, so that
+    /// it stops once it has processed the packets it has already received. The clients and the
+    /// servers connected to it must drop their senders to it as well.
+    /// # Errors
+    /// `ControllerError::UnknownDrone` if the drone does not exist or has already crashed,
+    /// `ControllerError::DroneDisconnected` if it has already stopped
+    pub fn crash(&mut self, drone: NodeId) -> Result<(), ControllerError> {
+        self.send_command(drone, DroneCommand::Crash)?;
+        for neighbor in self.neighbors(drone) {
+            self.unlink(drone, neighbor);
+        }
+        self.links.remove(&drone);
+        self.packet_send.remove(&drone);
+        if let Some(handle) = self.drones.remove(&drone) {
+            let crashed = CrashedDrone {
+                _command_send: handle.command_send,
+                event_recv: handle.event_recv,
+            };
+            self.crashed.insert(drone, crashed);
+        }
+        log::info!("drone {drone} crashed");
+        Ok(())
+    }
+
+    /// Connects two nodes, telling the drones among them to add a sender to the other node
+    /// # Errors
+    /// `ControllerError::SelfLink` if `a` and `b` are the same node,
+    /// `ControllerError::UnknownNode` if a node does not exist or has crashed,
+    /// `ControllerError::DroneDisconnected` if a drone of the link has stopped
+    pub fn add_link(&mut self, a: NodeId, b: NodeId) -> Result<(), ControllerError> {
+        if a == b {
+            return Err(ControllerError::SelfLink(a));
+        }
+        let a_send = self.node_sender(a)?;
+        let b_send = self.node_sender(b)?;
+        for (node, neighbor, sender) in [(a, b, b_send), (b, a, a_send)] {
+            if self.drones.contains_key(&node) {
+                self.send_command(node, DroneCommand::AddSender(neighbor, sender))?;
+            }
+            self.links.entry(node).or_default().insert(neighbor);
+        }
+        log::info!("link {a}-{b} added");
+        Ok(())
+    }
+
+    /// Disconnects two nodes, telling the drones among them to remove the sender to the other node
+    /// # Errors
+    /// `ControllerError::UnknownNode` if a node does not exist or has crashed,
+    /// `ControllerError::UnknownLink` if the two nodes are not connected,
+    /// `ControllerError::DroneDisconnected` if a drone of the link has stopped
+    pub fn remove_link(&mut self, a: NodeId, b: NodeId) -> Result<(), ControllerError> {
+        self.node_sender(a)?;
+        self.node_sender(b)?;
+        if !self.neighbors(a).contains(&b) {
+            return Err(ControllerError::UnknownLink(a, b));
+        }
+        for (node, neighbor) in [(a, b), (b, a)] {
+            if self.drones.contains_key(&node) {
+                self.send_command(node, DroneCommand::RemoveSender(neighbor))?;
+            }
+            if let Some(neighbors) = self.links.get_mut(&node) {
+                neighbors.remove(&neighbor);
+            }
+        }
+        log::info!("link {a}-{b} removed");
+        Ok(())
+    }
+
+    /// Sets the pdr of a drone
+    /// # Errors
+    /// `ControllerError::InvalidPdr` if `pdr` is not in range (0.0..=1.0), the other errors of
+    /// `SimulationController::crash`
+    pub fn set_pdr(&mut self, drone: NodeId, pdr: f32) -> Result<(), ControllerError> {
+        if !(0.0..=1.0).contains(&pdr) {
+            return Err(ControllerError::InvalidPdr(pdr));
+        }
+        self.send_command(drone, DroneCommand::SetPacketDropRate(pdr))
+    }
+
+    /// Sets the same pdr on every drone which has not crashed
+    /// # Errors
+    /// Same as `SimulationController::set_pdr`, the drones after the first failure are not told
+    pub fn set_pdr_everywhere(&mut self, pdr: f32) -> Result<(), ControllerError> {
+        self.drones()
+            .into_iter()
+            .try_for_each(|drone| self.set_pdr(drone, pdr))
+    }
+
+    /// Handles every event the drones have already sent, returning them with the id of their drone
+    pub fn process_events(&mut self) -> Vec<(NodeId, DroneEvent)> {
+        let mut events = Vec::new();
+        let mut stopped = Vec::new();
+        for (id, event_recv) in self.event_receivers() {
+            loop {
+                match event_recv.try_recv() {
+                    Ok(event) => events.push((*id, event)),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        stopped.push(*id);
+                        break;
+                    }
+                }
+            }
+        }
+        for id in stopped {
+            if self.crashed.remove(&id).is_some() {
+                log::info!("drone {id} stopped");
+            } else if self.drones.contains_key(&id) {
+                log::warn!("drone {id} stopped without crashing");
+                self.drones.remove(&id);
+            }
+        }
+        for (id, event) in &events {
+            self.handle_event(*id, event);
+        }
+        events
+    }
+
+    /// Waits up to `timeout` for an event, then handles it with all the others already sent, see
+    /// `SimulationController::process_events`
+    pub fn process_events_timeout(&mut self, timeout: Duration) -> Vec<(NodeId, DroneEvent)> {
+        let deadline = Instant::now() + timeout;
+        let mut select = Select::new();
+        for (_, event_recv) in self.event_receivers() {
+            select.recv(event_recv);
+        }
+        // a ready operation is not consumed, process_events will receive it
+        let _ = select.ready_deadline(deadline);
+        drop(select);
+        self.process_events()
+    }
+
+    fn event_receivers(&self) -> impl Iterator<Item = (&NodeId, &Receiver<DroneEvent>)> {
+        self.drones
+            .iter()
+            .map(|(id, handle)| (id, &handle.event_recv))
+            .chain(
+                self.crashed
+                    .iter()
+                    .map(|(id, crashed)| (id, &crashed.event_recv)),
+            )
+    }
+
+    fn handle_event(&self, drone: NodeId, event: &DroneEvent) {
+        match event {
+            DroneEvent::PacketSent(packet) => log::info!("drone {drone} sent {packet}"),
+            DroneEvent::PacketDropped(packet) => log::info!("drone {drone} dropped {packet}"),
+            DroneEvent::ControllerShortcut(packet) => {
+                let Some(destination) = packet.routing_header.hops.last() else {
+                    log::warn!("drone {drone} shortcut {packet}, which has no destination");
+                    return;
+                };
+                match self.packet_send.get(destination) {
+                    Some(sender) if sender.send(packet.clone()).is_ok() => {
+                        log::info!("delivered {packet} shortcut by drone {drone} to {destination}");
+                    }
+                    _ => log::warn!(
+                        "cannot deliver {packet} shortcut by drone {drone} to {destination}"
+                    ),
+                }
+            }
+        }
+    }
+
+    fn node_sender(&self, node: NodeId) -> Result<Sender<Packet>, ControllerError> {
+        self.packet_sender(node)
+            .ok_or(ControllerError::UnknownNode(node))
+    }
+
+    fn send_command(&self, drone: NodeId, command: DroneCommand) -> Result<(), ControllerError> {
+        let handle = self
+            .drones
+            .get(&drone)
+            .ok_or(ControllerError::UnknownDrone(drone))?;
+        handle
+            .command_send
+            .send(command)
+            .map_err(|_| ControllerError::DroneDisconnected(drone))
+    }
+
+    /// Tells `neighbor` that it is not connected to the crashing `drone` anymore
+    fn unlink(&mut self, drone: NodeId, neighbor: NodeId) {
+        if let Some(neighbors) = self.links.get_mut(&neighbor) {
+            neighbors.remove(&drone);
+        }
+        if self.drones.contains_key(&neighbor) {
+            if let Err(error) = self.send_command(neighbor, DroneCommand::RemoveSender(drone)) {
+                log::warn!("cannot remove {drone} from its neighbor: {error}");
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{MyDrone, State};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, PacketType};
+
+/// Number of packets for every `PacketType` variant
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PacketCounts {
+    pub fragment: u64,
+    pub ack: u64,
+    pub nack: u64,
+    pub flood_request: u64,
+    pub flood_response: u64,
+}
+
+impl PacketCounts {
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.fragment + self.ack + self.nack + self.flood_request + self.flood_response
+    }
+
+    #[must_use]
+    pub fn get(&self, pack_type: &PacketType) -> u64 {
+        match pack_type {
+            PacketType::MsgFragment(_) => self.fragment,
+            PacketType::Ack(_) => self.ack,
+            PacketType::Nack(_) => self.nack,
+            PacketType::FloodRequest(_) => self.flood_request,
+            PacketType::FloodResponse(_) => self.flood_response,
+        }
+    }
+
+    pub(crate) fn count(&mut self, pack_type: &PacketType) {
+        let counter = match pack_type {
+            PacketType::MsgFragment(_) => &mut self.fragment,
+            PacketType::Ack(_) => &mut self.ack,
+            PacketType::Nack(_) => &mut self.nack,
+            PacketType::FloodRequest(_) => &mut self.flood_request,
+            PacketType::FloodResponse(_) => &mut self.flood_response,
+        };
+        *counter += 1;
+    }
+}
+
+/// Number of nacks for every `NackType` variant
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NackCounts {
+    pub error_in_routing: u64,
+    pub destination_is_drone: u64,
+    pub dropped: u64,
+    pub unexpected_recipient: u64,
+}
+
+impl NackCounts {
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.error_in_routing + self.destination_is_drone + self.dropped + self.unexpected_recipient
+    }
+
+    #[must_use]
+    pub fn get(&self, nack_type: NackType) -> u64 {
+        match nack_type {
+            NackType::ErrorInRouting(_) => self.error_in_routing,
+            NackType::DestinationIsDrone => self.destination_is_drone,
+            NackType::Dropped => self.dropped,
+            NackType::UnexpectedRecipient(_) => self.unexpected_recipient,
+        }
+    }
+
+    pub(crate) fn count(&mut self, nack_type: NackType) {
+        let counter = match nack_type {
+            NackType::ErrorInRouting(_) => &mut self.error_in_routing,
+            NackType::DestinationIsDrone => &mut self.destination_is_drone,
+            NackType::Dropped => &mut self.dropped,
+            NackType::UnexpectedRecipient(_) => &mut self.unexpected_recipient,
+        };
+        *counter += 1;
+    }
+}
+
+/// Traffic on the link to a neighbor, since the channel to it was added
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinkStats {
+    /// Packets sent on the link
+    pub packets: u64,
+    /// Bytes of the fragments sent on the link, counted with their `length` so without the header
+    /// overhead of the `_wire_bytes` counters
+    pub bytes: u64,
+    /// Packets which could not be sent because the channel returned an error
+    pub failures: u64,
+    /// Packets dropped instead of being sent on the link: fragments dropped by the pdr, and
+    /// fragments and flood requests dropped when the outbound queue overflows or the link runs out
+    /// of bandwidth
+    pub drops: u64,
+    /// Bytes of the packets which had to wait for the bandwidth limit of the link, counted with
+    /// their `packet_size`, header overhead included
+    pub shaped_wire_bytes: u64,
+    /// Bytes of the packets dropped instead of being sent on the link, counted with their
+    /// `packet_size`, header overhead included
+    pub dropped_wire_bytes: u64,
+}
+
+/// What the drone has done since it was created, and its current configuration
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DroneStats {
+    /// Current state of the drone
+    pub state: State,
+    /// Current pdr of the drone, the one used by links without a pdr of their own
+    pub pdr: f32,
+    /// Packets received from the neighbors
+    pub received: PacketCounts,
+    /// Packets sent to a neighbor, including the nacks and flood responses created by the drone
+    pub forwarded: PacketCounts,
+    /// Packets dropped by the drop model, the pdr of a link, an overflowing outbound queue or the
+    /// bandwidth limit of a link
+    pub dropped: PacketCounts,
+    /// Nacks created by the drone
+    pub nacked: NackCounts,
+    /// Packets sent to the simulation controller because their next hop is not a neighbor
+    pub shortcut: PacketCounts,
+    /// Flood requests forwarded to the other neighbors, counted once for every flood
+    pub flood_requests_forwarded: u64,
+    /// Flood requests answered with a flood response
+    pub flood_requests_answered: u64,
+    /// Flood requests answered because their initiator went over the flood rate limit, these are
+    /// also counted in `flood_requests_answered`
+    pub flood_requests_rate_limited: u64,
+    /// Floods currently remembered by the flood cache
+    pub flood_cache_len: usize,
+    /// Floods evicted from the flood cache
+    pub flood_cache_evictions: u64,
+    /// Traffic on the link to every current neighbor
+    pub links: HashMap<NodeId, LinkStats>,
+}
+
+impl DroneStats {
+    /// Returns how many neighbors the drone has
+    #[must_use]
+    pub fn neighbors(&self) -> usize {
+        self.links.len()
+    }
+}
+
+/// Shared handle to the statistics of a drone, which can be read from any thread while the drone
+/// is running
+#[derive(Clone, Debug, Default)]
+pub struct DroneStatsHandle(Arc<Mutex<DroneStats>>);
+
+impl DroneStatsHandle {
+    /// Returns a copy of the current statistics of the drone
+    #[must_use]
+    pub fn snapshot(&self) -> DroneStats {
+        self.lock().clone()
+    }
+
+    /// Returns a copy of the current statistics of the link to `node_id`, `None` if it is not a
+    /// neighbor of the drone
+    #[must_use]
+    pub fn link(&self, node_id: NodeId) -> Option<LinkStats> {
+        self.lock().links.get(&node_id).copied()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, DroneStats> {
+        // the stats are plain counters, they are still meaningful if a thread panicked while
+        // holding the lock
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl MyDrone {
+    /// Returns a handle to the statistics of the drone, to read them while the drone is running
+    /// on another thread
+    #[must_use]
+    pub fn stats_handle(&self) -> DroneStatsHandle {
+        self.stats.clone()
+    }
+
+    /// Returns a copy of the current statistics of the drone
+    #[must_use]
+    pub fn stats(&self) -> DroneStats {
+        self.stats.snapshot()
+    }
+
+    /// Updates the statistics of the drone with `update`
+    pub(crate) fn record(&self, update: impl FnOnce(&mut DroneStats)) {
+        update(&mut self.stats.lock());
+    }
+}
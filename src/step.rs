@@ -0,0 +1,129 @@
+use crate::{ControlCommand, DroneError, MyDrone, State};
+use crossbeam_channel::{RecvError, TryRecvError};
+use wg_2024::controller::DroneCommand;
+use wg_2024::packet::Packet;
+
+/// What the drone did in a call to `MyDrone::step`
+#[derive(Debug, Clone)]
+pub enum StepOutcome {
+    /// Processed a command of the simulation controller
+    Command(DroneCommand),
+    /// Processed a command of the control channel
+    Control(ControlCommand),
+    /// Released the delayed packets whose delay is over and retried the queued ones
+    Timer,
+    /// Processed a packet
+    Packet(Packet),
+    /// The channel of the simulation controller or the packet channel is disconnected, the error
+    /// has been handled according to the fault policy
+    Disconnected,
+    /// The drone is crashing and all the senders to its packet channel are dropped, `run` would
+    /// return now
+    Finished,
+    /// There was nothing to do
+    Idle,
+}
+
+impl MyDrone {
+    /// Does exactly one of the things `run` waits for, if one is ready, without blocking: a command
+    /// of the simulation controller, then a command of the control channel, then the delayed and
+    /// queued packets when they are due, then a packet. Errors are handled according to the fault
+    /// policy, as in `run`.
+    ///
+    /// Packets waiting for room in the full channel of a neighbor are only retried when there is
+    /// nothing else to do, since there is no timer to wait for.
+    ///
+    /// # Panics
+    /// Same as `run`
+    pub fn step(&mut self) -> StepOutcome {
+        match self.controller_recv.try_recv() {
+            Ok(command) => return self.on_command(Ok(command)),
+            Err(TryRecvError::Disconnected) => return self.on_command(Err(RecvError)),
+            Err(TryRecvError::Empty) => {}
+        }
+        if let Ok(command) = self.control_recv.try_recv() {
+            return self.on_control_command(Ok(command));
+        }
+        let now = self.clock.now();
+        if self.next_deadline().is_some_and(|deadline| deadline <= now) {
+            return self.on_timer_fired();
+        }
+        if self.accepts_packets() {
+            match self.packet_recv.try_recv() {
+                Ok(packet) => return self.on_packet(Ok(packet)),
+                Err(TryRecvError::Disconnected) => return self.on_packet(Err(RecvError)),
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        if self.is_congested() {
+            return self.on_timer_fired();
+        }
+        StepOutcome::Idle
+    }
+
+    pub(crate) fn on_command(
+        &mut self,
+        command_res: Result<DroneCommand, RecvError>,
+    ) -> StepOutcome {
+        log::info!("Received controller command: {command_res:?}");
+        let (result, outcome) = match command_res {
+            Ok(command) => (
+                self.process_command(command.clone()),
+                StepOutcome::Command(command),
+            ),
+            Err(_err) => (
+                Err(DroneError::ControllerDisconnected),
+                StepOutcome::Disconnected,
+            ),
+        };
+        if let Err(error) = result {
+            self.handle_error(error);
+        }
+        outcome
+    }
+
+    pub(crate) fn on_control_command(
+        &mut self,
+        control_res: Result<ControlCommand, RecvError>,
+    ) -> StepOutcome {
+        log::info!("Received control command: {control_res:?}");
+        // the drone owns a sender for this channel, so it can never be disconnected
+        let Ok(command) = control_res else {
+            return StepOutcome::Idle;
+        };
+        if let Err(error) = self.process_control_command(command.clone()) {
+            self.handle_error(error);
+        }
+        StepOutcome::Control(command)
+    }
+
+    pub(crate) fn on_timer_fired(&mut self) -> StepOutcome {
+        if let Err(error) = self.on_timer() {
+            self.handle_error(error);
+        }
+        StepOutcome::Timer
+    }
+
+    pub(crate) fn on_packet(&mut self, packet_res: Result<Packet, RecvError>) -> StepOutcome {
+        log::info!("Received packet: {packet_res:?}");
+        match packet_res {
+            Err(_err) => match &self.state {
+                State::Working => {
+                    self.handle_error(DroneError::PacketChannelDisconnected);
+                    StepOutcome::Disconnected
+                }
+                State::Crashing => {
+                    log::info!("Drone is finally crashing, no more packets will be processed");
+                    StepOutcome::Finished
+                }
+            },
+            Ok(packet) => {
+                log::info!("Processing packet {packet}");
+                if let Err(error) = self.process_packet(packet.clone()) {
+                    self.handle_error(error);
+                }
+                StepOutcome::Packet(packet)
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use wg_2024::config::Config;
+use wg_2024::network::NodeId;
+
+/// Most drones a client can be connected to
+pub const MAX_CLIENT_DRONES: usize = 2;
+/// Fewest drones a server can be connected to
+pub const MIN_SERVER_DRONES: usize = 2;
+
+/// A problem of a topology which prevents it from being a valid WG-2024 network
+#[derive(Debug, Clone, PartialEq)]
+pub enum TopologyIssue {
+    /// More than one node has this id
+    DuplicateId(NodeId),
+    /// A node is connected to itself, `MyDrone::add_channel` refuses such a channel
+    SelfLink(NodeId),
+    /// A node is connected to an id which is not in the topology
+    UnknownNode { node: NodeId, neighbor: NodeId },
+    /// A client or a server is connected to a node which is not a drone
+    NotADrone { host: NodeId, neighbor: NodeId },
+    /// `node` is connected to `neighbor`, but `neighbor` is not connected to `node`
+    AsymmetricLink { node: NodeId, neighbor: NodeId },
+    /// The pdr of a drone is not in range (0.0..=1.0)
+    InvalidPdr { drone: NodeId, pdr: f32 },
+    /// A client is connected to more than `MAX_CLIENT_DRONES` drones
+    TooManyDrones { client: NodeId, drones: usize },
+    /// A server is connected to fewer than `MIN_SERVER_DRONES` drones
+    TooFewDrones { server: NodeId, drones: usize },
+    /// A node is not connected to anything, and no node is connected to it
+    Isolated(NodeId),
+    /// The network is split into parts which cannot reach each other through the drones, each with
+    /// its sorted nodes. Hosts do not forward packets, so a host connected to drones of different
+    /// parts is only in the first one, and the isolated nodes are not in any part.
+    Partitioned(Vec<Vec<NodeId>>),
+}
+
+impl Display for TopologyIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TopologyIssue::DuplicateId(id) => write!(f, "there is more than one node with id {id}"),
+            TopologyIssue::SelfLink(id) => write!(f, "node {id} is connected to itself"),
+            TopologyIssue::UnknownNode { node, neighbor } => {
+                write!(f, "node {node} is connected to {neighbor}, which does not exist")
+            }
+            TopologyIssue::NotADrone { host, neighbor } => write!(
+                f,
+                "node {host} is connected to {neighbor}, but clients and servers can only be connected to drones"
+            ),
+            TopologyIssue::AsymmetricLink { node, neighbor } => write!(
+                f,
+                "node {node} is connected to {neighbor}, but {neighbor} is not connected to {node}"
+            ),
+            TopologyIssue::InvalidPdr { drone, pdr } => write!(
+                f,
+                "drone {drone} has a pdr of {pdr}, which is not in range (0.0..=1.0)"
+            ),
+            TopologyIssue::TooManyDrones { client, drones } => write!(
+                f,
+                "client {client} is connected to {drones} drones, more than {MAX_CLIENT_DRONES}"
+            ),
+            TopologyIssue::TooFewDrones { server, drones } => write!(
+                f,
+                "server {server} is connected to {drones} drones, fewer than {MIN_SERVER_DRONES}"
+            ),
+            TopologyIssue::Isolated(id) => write!(f, "node {id} is not connected to anything"),
+            TopologyIssue::Partitioned(parts) => {
+                write!(f, "the network is split into {} parts:", parts.len())?;
+                for part in parts {
+                    write!(f, " {part:?}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for TopologyIssue {}
+
+/// Reports every problem of the topology at once, in the order of the variants of
+/// `TopologyIssue`; the topology is valid if there is none
+#[must_use]
+pub fn validate_topology(config: &Config) -> Vec<TopologyIssue> {
+    let links = links(config);
+    let mut issues = Vec::new();
+
+    let mut ids = HashSet::new();
+    let mut duplicates = Vec::new();
+    for (id, _) in &links {
+        if !ids.insert(*id) && !duplicates.contains(id) {
+            duplicates.push(*id);
+        }
+    }
+    issues.extend(duplicates.into_iter().map(TopologyIssue::DuplicateId));
+
+    let neighbors: HashMap<NodeId, &[NodeId]> = links.iter().copied().collect();
+    let drones: HashSet<NodeId> = config.drone.iter().map(|drone| drone.id).collect();
+    let mut self_links = Vec::new();
+    let mut link_issues = Vec::new();
+    for (node, node_neighbors) in &links {
+        for neighbor in *node_neighbors {
+            if neighbor == node {
+                if !self_links.contains(node) {
+                    self_links.push(*node);
+                }
+                continue;
+            }
+            let Some(back) = neighbors.get(neighbor) else {
+                link_issues.push(TopologyIssue::UnknownNode {
+                    node: *node,
+                    neighbor: *neighbor,
+                });
+                continue;
+            };
+            if !drones.contains(node) && !drones.contains(neighbor) {
+                link_issues.push(TopologyIssue::NotADrone {
+                    host: *node,
+                    neighbor: *neighbor,
+                });
+            }
+            if !back.contains(node) {
+                link_issues.push(TopologyIssue::AsymmetricLink {
+                    node: *node,
+                    neighbor: *neighbor,
+                });
+            }
+        }
+    }
+    issues.extend(self_links.into_iter().map(TopologyIssue::SelfLink));
+    issues.extend(link_issues);
+
+    issues.extend(
+        config
+            .drone
+            .iter()
+            .filter(|drone| !(0.0..=1.0).contains(&drone.pdr))
+            .map(|drone| TopologyIssue::InvalidPdr {
+                drone: drone.id,
+                pdr: drone.pdr,
+            }),
+    );
+    let connected_drones = |connected: &[NodeId]| {
+        connected
+            .iter()
+            .filter(|id| drones.contains(id))
+            .collect::<HashSet<_>>()
+            .len()
+    };
+    for client in &config.client {
+        let count = connected_drones(&client.connected_drone_ids);
+        if count > MAX_CLIENT_DRONES {
+            issues.push(TopologyIssue::TooManyDrones {
+                client: client.id,
+                drones: count,
+            });
+        }
+    }
+    for server in &config.server {
+        let count = connected_drones(&server.connected_drone_ids);
+        // an isolated server is only reported as such
+        if count < MIN_SERVER_DRONES && !server.connected_drone_ids.is_empty() {
+            issues.push(TopologyIssue::TooFewDrones {
+                server: server.id,
+                drones: count,
+            });
+        }
+    }
+
+    let listed: HashSet<NodeId> = links
+        .iter()
+        .flat_map(|(_, node_neighbors)| node_neighbors.iter().copied())
+        .collect();
+    let mut isolated: Vec<NodeId> = Vec::new();
+    for (id, node_neighbors) in &links {
+        if node_neighbors.is_empty() && !listed.contains(id) && !isolated.contains(id) {
+            isolated.push(*id);
+        }
+    }
+    issues.extend(isolated.iter().copied().map(TopologyIssue::Isolated));
+    let parts = parts(config, &isolated);
+    if parts.len() > 1 {
+        issues.push(TopologyIssue::Partitioned(parts));
+    }
+    issues
+}
+
+/// Every node of the topology with the ids it is connected to, drones first
+pub(crate) fn links(config: &Config) -> Vec<(NodeId, &[NodeId])> {
+    let drones = config
+        .drone
+        .iter()
+        .map(|drone| (drone.id, drone.connected_node_ids.as_slice()));
+    let clients = config
+        .client
+        .iter()
+        .map(|client| (client.id, client.connected_drone_ids.as_slice()));
+    let servers = config
+        .server
+        .iter()
+        .map(|server| (server.id, server.connected_drone_ids.as_slice()));
+    drones.chain(clients).chain(servers).collect()
+}
+
+/// The groups of nodes which can reach each other through the drones, leaving out `isolated`.
+/// Links are followed in both directions, so an asymmetric link does not split the network.
+fn parts(config: &Config, isolated: &[NodeId]) -> Vec<Vec<NodeId>> {
+    let drones: HashSet<NodeId> = config.drone.iter().map(|drone| drone.id).collect();
+    let links = links(config);
+    let ids: HashSet<NodeId> = links.iter().map(|(id, _)| *id).collect();
+    let mut edges: HashMap<NodeId, HashSet<NodeId>> = HashMap::new();
+    for (node, node_neighbors) in &links {
+        let node = *node;
+        for neighbor in node_neighbors.iter().filter(|id| ids.contains(id)) {
+            edges.entry(node).or_default().insert(*neighbor);
+            edges.entry(*neighbor).or_default().insert(node);
+        }
+    }
+
+    let mut part_of: HashMap<NodeId, usize> = HashMap::new();
+    let mut parts: Vec<Vec<NodeId>> = Vec::new();
+    let no_edges = HashSet::new();
+    let edges_of = |id: &NodeId| edges.get(id).unwrap_or(&no_edges);
+    for (start, _) in links {
+        if isolated.contains(&start) || part_of.contains_key(&start) {
+            continue;
+        }
+        if !drones.contains(&start) {
+            // a host connected to a drone joins the part of the drone, when that part is found
+            if edges_of(&start).iter().any(|id| drones.contains(id)) {
+                continue;
+            }
+        }
+        let index = parts.len();
+        let mut part = vec![start];
+        part_of.insert(start, index);
+        let mut to_visit = VecDeque::from([start]);
+        while let Some(id) = to_visit.pop_front() {
+            if id != start && !drones.contains(&id) {
+                continue;
+            }
+            for neighbor in edges_of(&id) {
+                if !part_of.contains_key(neighbor) && !isolated.contains(neighbor) {
+                    part_of.insert(*neighbor, index);
+                    part.push(*neighbor);
+                    to_visit.push_back(*neighbor);
+                }
+            }
+        }
+        part.sort_unstable();
+        parts.push(part);
+    }
+    parts
+}
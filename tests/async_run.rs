@@ -0,0 +1,49 @@
+#![cfg(feature = "tokio")]
+
+use std::{collections::HashMap, time::Duration};
+
+use common::{chain::Chain, create_channels};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::MyDrone;
+use wg_2024::{controller::DroneCommand, network::NodeId, packet::Packet};
+
+pub mod common;
+
+const CHAIN_LENGTH: NodeId = 200;
+
+/// topology: 0-1-2-...-200-201, the drones 1 to 200 run on a runtime with 2 threads
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn many_drones_on_few_threads() {
+    let mut chain = Chain::new(CHAIN_LENGTH);
+    for mut my_drone in std::mem::take(&mut chain.drones) {
+        tokio::spawn(async move { my_drone.run_async().await });
+    }
+
+    chain.send_fragment();
+    let last_node = chain.last_node();
+    let received =
+        tokio::task::spawn_blocking(move || last_node.recv_timeout(Duration::from_secs(5)))
+            .await
+            .unwrap()
+            .unwrap();
+    assert_eq!(received, chain.delivered_fragment());
+}
+
+#[tokio::test]
+async fn run_async_returns_after_crash() {
+    let (event_send, _event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+    let (s0, _r0) = unbounded::<Packet>();
+    let mut my_drone = MyDrone::builder(1, event_send, command_recv, packet_recv, HashMap::new())
+        .neighbor(0, s0)
+        .build()
+        .unwrap();
+    let handle = tokio::spawn(async move { my_drone.run_async().await });
+
+    command_send.send(DroneCommand::Crash).unwrap();
+    drop(packet_send);
+    tokio::time::timeout(Duration::from_secs(1), handle)
+        .await
+        .unwrap()
+        .unwrap();
+}
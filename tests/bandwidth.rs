@@ -0,0 +1,114 @@
+use std::{collections::HashMap, time::Duration};
+
+use common::{
+    create_channels,
+    expect::{expect_no_packet, expect_one_packet, try_send_packet},
+    packetbuilder::PacketBuilder,
+    packets::{forwarded, fragment},
+    start_drone_thread,
+};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::{
+    packet_size, DroneError, ExcessPolicy, LinkBandwidth, LinkStats, MockClock, MyDrone,
+    MAX_PACKET_SIZE,
+};
+use wg_2024::packet::{NackType, Packet};
+
+pub mod common;
+
+/// topology: 0-1-2, the link to 2 sends a full fragment every 100ms
+#[test_log::test]
+fn excess_is_queued() {
+    let (event_send, _event_recv, _command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let senders = HashMap::from([(0, s0), (2, s2)]);
+
+    let bandwidth = LinkBandwidth::new(f64::from(MAX_PACKET_SIZE) * 10.0, MAX_PACKET_SIZE).unwrap();
+    let clock = MockClock::new();
+    let my_drone = MyDrone::builder(1, event_send, command_recv, packet_recv, senders)
+        .clock(clock.clone())
+        .neighbor_bandwidth(2, bandwidth)
+        .build()
+        .unwrap();
+    let stats = my_drone.stats_handle();
+    let _handle = start_drone_thread(my_drone);
+
+    for session_id in 1..=3 {
+        try_send_packet(&packet_send, fragment(vec![0, 1, 2], session_id));
+    }
+    expect_one_packet(&r2, &forwarded(vec![0, 1, 2], 1));
+    for session_id in 2..=3 {
+        clock.advance(Duration::from_millis(99));
+        expect_no_packet(&r2);
+        clock.advance(Duration::from_millis(1));
+        expect_one_packet(&r2, &forwarded(vec![0, 1, 2], session_id));
+    }
+    expect_no_packet(&r0);
+
+    assert_eq!(
+        stats.link(2),
+        Some(LinkStats {
+            packets: 3,
+            bytes: 3 * 128,
+            shaped_wire_bytes: 2 * u64::from(MAX_PACKET_SIZE),
+            ..LinkStats::default()
+        })
+    );
+}
+
+/// topology: 0-1-2, the link to 2 has room for a single full fragment
+#[test_log::test]
+fn excess_is_dropped() {
+    let (event_send, _event_recv, _command_send, command_recv, _packet_send, packet_recv) =
+        create_channels();
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let senders = HashMap::from([(0, s0), (2, s2)]);
+
+    let bandwidth = LinkBandwidth::new(1.0, MAX_PACKET_SIZE)
+        .unwrap()
+        .excess(ExcessPolicy::Drop);
+    let mut my_drone = MyDrone::builder(1, event_send, command_recv, packet_recv, senders)
+        .neighbor_bandwidth(2, bandwidth)
+        .build()
+        .unwrap();
+
+    my_drone.process_packet(fragment(vec![0, 1, 2], 1)).unwrap();
+    my_drone.process_packet(fragment(vec![0, 1, 2], 2)).unwrap();
+    expect_one_packet(&r2, &forwarded(vec![0, 1, 2], 1));
+    expect_one_packet(
+        &r0,
+        &PacketBuilder::new_nack(vec![1, 0], NackType::Dropped)
+            .session_id(2)
+            .build(),
+    );
+
+    assert_eq!(
+        my_drone.stats_handle().link(2),
+        Some(LinkStats {
+            packets: 1,
+            bytes: 128,
+            drops: 1,
+            dropped_wire_bytes: u64::from(packet_size(&fragment(vec![0, 1, 2], 2))),
+            ..LinkStats::default()
+        })
+    );
+}
+
+#[test]
+fn invalid_bandwidth() {
+    assert!(matches!(
+        LinkBandwidth::new(0.0, MAX_PACKET_SIZE),
+        Err(DroneError::InvalidBandwidth(_))
+    ));
+    assert!(matches!(
+        LinkBandwidth::new(f64::INFINITY, MAX_PACKET_SIZE),
+        Err(DroneError::InvalidBandwidth(_))
+    ));
+    assert!(matches!(
+        LinkBandwidth::new(1000.0, MAX_PACKET_SIZE - 1),
+        Err(DroneError::InvalidBandwidth(_))
+    ));
+}
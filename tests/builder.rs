@@ -0,0 +1,120 @@
+use std::{collections::HashMap, num::NonZeroUsize, time::Duration};
+
+use common::{
+    create_channels,
+    expect::{expect_no_packet, expect_one_packet, try_send_packet},
+    packetbuilder::PacketBuilder,
+    start_drone_thread,
+};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::{
+    DroneError, EveryNth, FaultAction, FaultPolicy, MyDrone, MyDroneBuilder, PdrSchedule,
+};
+use wg_2024::packet::{NackType, Packet};
+
+pub mod common;
+
+/// topology: 0-1-2 and 1-3, the link 1-3 drops every fragment and the drop model of the drone
+/// drops every other fragment
+#[test_log::test]
+fn build_drone() {
+    let (event_send, _event_recv, _command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let (s3, r3) = unbounded::<Packet>();
+
+    let my_drone = MyDroneBuilder::new(
+        1,
+        event_send,
+        command_recv,
+        packet_recv,
+        HashMap::from([(0, s0)]),
+    )
+    .neighbor(2, s2)
+    .neighbor(3, s3)
+    .seed(42)
+    .drop_model(EveryNth::new(2))
+    .neighbor_pdr(3, 1.0)
+    .fault_policy(FaultPolicy::uniform(FaultAction::DropAndLog))
+    .flood_cache(
+        NonZeroUsize::new(16).unwrap(),
+        Some(Duration::from_secs(60)),
+    )
+    .build()
+    .unwrap();
+    assert_eq!(my_drone.stats().neighbors(), 3);
+    let _handle = start_drone_thread(my_drone);
+
+    let to_2 = PacketBuilder::new_fragment(vec![0, 1, 2]).build();
+    let forwarded = PacketBuilder::new_fragment(vec![0, 1, 2])
+        .hop_index(2)
+        .build();
+    let dropped = PacketBuilder::new_nack(vec![1, 0], NackType::Dropped).build();
+
+    try_send_packet(&packet_send, to_2.clone());
+    expect_one_packet(&r2, &forwarded);
+    try_send_packet(&packet_send, to_2);
+    expect_one_packet(&r0, &dropped);
+    expect_no_packet(&r2);
+
+    try_send_packet(
+        &packet_send,
+        PacketBuilder::new_fragment(vec![0, 1, 3]).build(),
+    );
+    expect_one_packet(&r0, &dropped);
+    expect_no_packet(&r3);
+}
+
+#[test]
+fn builder_errors() {
+    let builder = || {
+        let (event_send, _event_recv, _command_send, command_recv, _packet_send, packet_recv) =
+            create_channels();
+        let (s2, _r2) = unbounded::<Packet>();
+        MyDrone::builder(
+            1,
+            event_send,
+            command_recv,
+            packet_recv,
+            HashMap::from([(2, s2)]),
+        )
+    };
+
+    assert!(builder().pdr(0.3).build().is_ok());
+    assert!(matches!(
+        builder().pdr(1.5).build(),
+        Err(DroneError::InvalidPdr(_))
+    ));
+    assert!(matches!(
+        builder().neighbor(1, unbounded().0).build(),
+        Err(DroneError::SelfChannel(1))
+    ));
+    assert!(matches!(
+        builder().neighbor_pdr(3, 0.5).build(),
+        Err(DroneError::UnknownNeighbor(3))
+    ));
+    assert!(matches!(
+        builder().neighbor_pdr(2, -0.5).build(),
+        Err(DroneError::InvalidPdr(_))
+    ));
+    assert!(matches!(
+        builder()
+            .pdr_schedule(PdrSchedule::Piecewise(vec![]))
+            .build(),
+        Err(DroneError::InvalidPdrSchedule(_))
+    ));
+    let ramp = PdrSchedule::Ramp {
+        from: 0.0,
+        to: 1.0,
+        duration: Duration::from_secs(1),
+    };
+    assert!(matches!(
+        builder()
+            .pdr_schedule(ramp)
+            .drop_model(EveryNth::new(2))
+            .build(),
+        Err(DroneError::InvalidPdrSchedule(_))
+    ));
+}
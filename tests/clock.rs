@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+use null_pointer_drone::{Clock, MockClock};
+
+#[test]
+fn mock_clock() {
+    let clock = MockClock::new();
+    let start = clock.now();
+    let timer = clock.at(start + Duration::from_secs(10));
+
+    // the clones share the same time
+    clock.clone().advance(Duration::from_secs(9));
+    assert_eq!(clock.now(), start + Duration::from_secs(9));
+    assert!(timer.try_recv().is_err());
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(timer.try_recv(), Ok(start + Duration::from_secs(10)));
+
+    // a deadline already reached fires right away
+    assert!(clock.at(start).try_recv().is_ok());
+}
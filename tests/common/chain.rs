@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use null_pointer_drone::MyDrone;
+use wg_2024::{
+    controller::{DroneCommand, DroneEvent},
+    network::NodeId,
+    packet::Packet,
+};
+
+use super::packetbuilder::PacketBuilder;
+
+/// topology: 0-1-2-...-length-(length + 1), the drones 1 to `length` are built but not started and
+/// the nodes at the two ends are only channels
+pub struct Chain {
+    pub length: NodeId,
+    pub drones: Vec<MyDrone>,
+    channels: Vec<(Sender<Packet>, Receiver<Packet>)>,
+    // the drones panic if the simulation controller channel is disconnected
+    command_senders: Vec<Sender<DroneCommand>>,
+    _event_recv: Receiver<DroneEvent>,
+}
+
+impl Chain {
+    pub fn new(length: NodeId) -> Self {
+        let channels: Vec<(Sender<Packet>, Receiver<Packet>)> =
+            (0..=length + 1).map(|_| unbounded()).collect();
+        let (event_send, event_recv) = unbounded::<DroneEvent>();
+        let mut command_senders = Vec::new();
+        let mut drones = Vec::new();
+
+        for id in 1..=length {
+            let (command_send, command_recv) = unbounded::<DroneCommand>();
+            command_senders.push(command_send);
+            let senders = HashMap::from([
+                (id - 1, channels[usize::from(id - 1)].0.clone()),
+                (id + 1, channels[usize::from(id + 1)].0.clone()),
+            ]);
+            let my_drone = MyDrone::builder(
+                id,
+                event_send.clone(),
+                command_recv,
+                channels[usize::from(id)].1.clone(),
+                senders,
+            )
+            .build()
+            .unwrap();
+            drones.push(my_drone);
+        }
+        Self {
+            length,
+            drones,
+            channels,
+            command_senders,
+            _event_recv: event_recv,
+        }
+    }
+
+    fn hops(&self) -> Vec<NodeId> {
+        (0..=self.length + 1).collect()
+    }
+
+    /// sends a fragment from 0 to the last node through every drone
+    pub fn send_fragment(&self) {
+        self.channels[1]
+            .0
+            .send(PacketBuilder::new_fragment(self.hops()).build())
+            .unwrap();
+    }
+
+    /// the fragment of `Chain::send_fragment` as the last node receives it
+    pub fn delivered_fragment(&self) -> Packet {
+        PacketBuilder::new_fragment(self.hops())
+            .hop_index(usize::from(self.length) + 1)
+            .build()
+    }
+
+    /// the packet receiver of the last node
+    pub fn last_node(&self) -> Receiver<Packet> {
+        self.channels[usize::from(self.length + 1)].1.clone()
+    }
+
+    /// crashes every drone as the simulation controller would, removing it from its neighbors,
+    /// and drops the channels of the chain so that all of them stop. The command senders are kept
+    /// until the chain is dropped, the drones must be joined before that.
+    pub fn crash(&mut self) {
+        for (i, command_send) in self.command_senders.iter().enumerate() {
+            let id = NodeId::try_from(i + 1).unwrap();
+            command_send.send(DroneCommand::Crash).unwrap();
+            command_send
+                .send(DroneCommand::RemoveSender(id - 1))
+                .unwrap();
+            command_send
+                .send(DroneCommand::RemoveSender(id + 1))
+                .unwrap();
+        }
+        self.channels.clear();
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
+use null_pointer_drone::MyDrone;
+use wg_2024::{
+    controller::{DroneCommand, DroneEvent},
+    drone::Drone,
+    packet::{Packet, PacketType},
+};
+
+use super::create_channels;
+
+/// topology: 0-1-2, drone 1 is not started so the test can drive it. The event receiver is kept
+/// alive for the drone to send packets, and the senders to the drone so that its channels are not
+/// disconnected.
+pub struct Line {
+    pub drone: MyDrone,
+    pub command_send: Sender<DroneCommand>,
+    pub packet_send: Sender<Packet>,
+    pub r0: Receiver<Packet>,
+    pub r2: Receiver<Packet>,
+    _event_recv: Receiver<DroneEvent>,
+}
+
+impl Line {
+    /// drone 1 has a pdr of 0.0 and is then set up by `configure`
+    pub fn new(configure: impl FnOnce(MyDrone) -> MyDrone) -> Self {
+        Self::with_channel_to_2(unbounded(), configure)
+    }
+
+    /// same as `Line::new`, with room for only `capacity` packets in the channel to 2
+    pub fn bounded(capacity: usize, configure: impl FnOnce(MyDrone) -> MyDrone) -> Self {
+        Self::with_channel_to_2(bounded(capacity), configure)
+    }
+
+    fn with_channel_to_2(
+        (s2, r2): (Sender<Packet>, Receiver<Packet>),
+        configure: impl FnOnce(MyDrone) -> MyDrone,
+    ) -> Self {
+        let (event_send, event_recv, command_send, command_recv, packet_send, packet_recv) =
+            create_channels();
+        let (s0, r0) = unbounded::<Packet>();
+        let senders = HashMap::from([(0, s0), (2, s2)]);
+
+        let drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0);
+        Self {
+            drone: configure(drone),
+            command_send,
+            packet_send,
+            r0,
+            r2,
+            _event_recv: event_recv,
+        }
+    }
+
+    /// Makes drone 1 process a flood request coming from 0 and returns whether it forwarded it to
+    /// 2 or answered with a flood response
+    pub fn is_forwarded(&mut self, flood_request: Packet) -> bool {
+        self.drone.process_packet(flood_request).unwrap();
+
+        match (self.r0.try_recv(), self.r2.try_recv()) {
+            (Err(_), Ok(p)) if matches!(p.pack_type, PacketType::FloodRequest(_)) => true,
+            (Ok(p), Err(_)) if matches!(p.pack_type, PacketType::FloodResponse(_)) => false,
+            other => panic!("unexpected packets {other:?}"),
+        }
+    }
+}
@@ -9,8 +9,11 @@ use wg_2024::{
     packet::{Fragment, Packet},
 };
 
+pub mod chain;
 pub mod expect;
+pub mod line;
 pub mod packetbuilder;
+pub mod packets;
 
 #[allow(clippy::type_complexity)]
  pub fn create_channels() -> (
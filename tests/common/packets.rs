@@ -0,0 +1,18 @@
+use wg_2024::{network::NodeId, packet::Packet};
+
+use super::packetbuilder::PacketBuilder;
+
+/// a fragment sent by `hops[0]` to drone `hops[1]`
+pub fn fragment(hops: Vec<NodeId>, session_id: u64) -> Packet {
+    PacketBuilder::new_fragment(hops)
+        .session_id(session_id)
+        .build()
+}
+
+/// the fragment of `fragment` as `hops[2]` receives it
+pub fn forwarded(hops: Vec<NodeId>, session_id: u64) -> Packet {
+    PacketBuilder::new_fragment(hops)
+        .hop_index(2)
+        .session_id(session_id)
+        .build()
+}
@@ -1,17 +1,20 @@
 use std::collections::HashMap;
 
 use common::{
-    create_channels,
-    expect::{expect_event, expect_no_packet, expect_one_event, expect_one_packet},
+    create_channels, default_fragment,
+    expect::{
+        expect_event, expect_no_packet, expect_one_event, expect_one_packet, try_send_packet,
+    },
     packetbuilder::PacketBuilder,
     start_drone_thread,
 };
 use crossbeam_channel::unbounded;
 use null_pointer_drone::MyDrone;
+use rand::RngCore;
 use wg_2024::{
     controller::DroneEvent,
     drone::Drone,
-    packet::{NackType, Packet},
+    packet::{NackType, Packet, PacketType},
 };
 
 pub mod common;
@@ -53,3 +56,103 @@ fn expect_drop() {
     expect_event(&event_recv, &DroneEvent::PacketDropped(packet));
     expect_one_event(&event_recv, &DroneEvent::PacketSent(expected));
 }
+
+/// sends `n_frags` fragments 0 -> (1) -> 2 through a drone with pdr 0.5 created with the given
+/// seed and returns, for each fragment, whether it got dropped
+fn dropped_fragments_with_seed(seed: u64, n_frags: u64) -> Vec<bool> {
+    let (event_send, _event_recv, _controller_send, controller_recv, _packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+
+    let mut my_drone = MyDrone::with_seed(
+        1,
+        event_send,
+        controller_recv,
+        packet_recv,
+        senders,
+        0.5,
+        seed,
+    );
+
+    (0..n_frags)
+        .map(|idx| {
+            let packet = PacketBuilder::new(
+                PacketType::MsgFragment(default_fragment(idx, n_frags)),
+                vec![0, 1, 2],
+            )
+            .build();
+            my_drone.process_packet(packet).unwrap();
+            match (r0.try_recv(), r2.try_recv()) {
+                (Ok(_nack), Err(_)) => true,
+                (Err(_), Ok(_fragment)) => false,
+                other => panic!("expected either a nack or a forwarded fragment, got {other:?}"),
+            }
+        })
+        .collect()
+}
+
+/// two drones created with the same seed drop exactly the same fragments
+#[test_log::test]
+fn seeded_drops_are_reproducible() {
+    let first = dropped_fragments_with_seed(42, 64);
+    let second = dropped_fragments_with_seed(42, 64);
+    assert_eq!(first, second);
+    assert!(first.contains(&true) && first.contains(&false));
+
+    let other_seed = dropped_fragments_with_seed(43, 64);
+    assert_ne!(first, other_seed);
+}
+
+/// an rng that always returns zero, so every dice roll gives 0.0
+struct ZeroRng;
+
+impl RngCore for ZeroRng {
+    fn next_u32(&mut self) -> u32 {
+        0
+    }
+    fn next_u64(&mut self) -> u64 {
+        0
+    }
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        dst.fill(0);
+    }
+}
+
+/// a user supplied rng is used to roll the dice: with a roll of 0.0 every fragment is dropped
+/// as soon as the pdr is not zero
+#[test_log::test]
+fn user_supplied_rng() {
+    let (event_send, event_recv, _controller_send, controller_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+
+    let my_drone = MyDrone::with_rng(
+        1,
+        event_send,
+        controller_recv,
+        packet_recv,
+        senders,
+        0.01,
+        ZeroRng,
+    );
+    let _handle = start_drone_thread(my_drone);
+
+    let packet = PacketBuilder::new_fragment(vec![0, 1, 2]).build();
+    try_send_packet(&packet_send, packet.clone());
+
+    let expected = PacketBuilder::new_nack(vec![1, 0], NackType::Dropped).build();
+    expect_one_packet(&r0, &expected);
+    expect_no_packet(&r2);
+    expect_event(&event_recv, &DroneEvent::PacketDropped(packet));
+    expect_one_event(&event_recv, &DroneEvent::PacketSent(expected));
+}
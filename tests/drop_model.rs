@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use common::{
+    create_channels, default_fragment,
+    expect::{expect_one_packet, try_send_command, try_send_packet},
+    packetbuilder::PacketBuilder,
+    start_drone_thread,
+};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::{Bernoulli, DropModel, EveryNth, GilbertElliott, MyDrone, Scripted};
+use rand::{rngs::StdRng, SeedableRng};
+use wg_2024::{
+    controller::DroneCommand,
+    drone::Drone,
+    packet::{Packet, PacketType},
+};
+
+pub mod common;
+
+/// creates drone 1 with neighbors 0 and 2 and the given drop model, then processes `n_frags`
+/// fragments 0 -> (1) -> 2 and returns, for each of them, whether it got dropped
+fn dropped_fragments(drop_model: impl DropModel + 'static, n_frags: u64) -> Vec<bool> {
+    let (event_send, _event_recv, _command_send, command_recv, _packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+
+    let mut my_drone =
+        MyDrone::with_seed(1, event_send, command_recv, packet_recv, senders, 0.0, 7)
+            .with_drop_model(drop_model);
+
+    (0..n_frags)
+        .map(|idx| {
+            let packet = PacketBuilder::new(
+                PacketType::MsgFragment(default_fragment(idx, n_frags)),
+                vec![0, 1, 2],
+            )
+            .build();
+            my_drone.process_packet(packet).unwrap();
+            match (r0.try_recv(), r2.try_recv()) {
+                (Ok(_nack), Err(_)) => true,
+                (Err(_), Ok(_fragment)) => false,
+                other => panic!("expected either a nack or a forwarded fragment, got {other:?}"),
+            }
+        })
+        .collect()
+}
+
+#[test_log::test]
+fn every_nth() {
+    let dropped = dropped_fragments(EveryNth::new(3), 9);
+    assert_eq!(
+        dropped,
+        vec![false, false, true, false, false, true, false, false, true]
+    );
+
+    let dropped = dropped_fragments(EveryNth::new(0), 5);
+    assert_eq!(dropped, vec![false; 5]);
+}
+
+#[test_log::test]
+fn scripted() {
+    let dropped = dropped_fragments(Scripted::new([0, 3, 4]), 6);
+    assert_eq!(dropped, vec![true, false, false, true, true, false]);
+}
+
+/// with a bad state that never recovers and always loses, everything after the first switch is
+/// dropped
+#[test_log::test]
+fn gilbert_elliott_burst() {
+    let model = GilbertElliott::new(0.2, 0.0, 0.0, 1.0).unwrap();
+    let dropped = dropped_fragments(model, 100);
+    let first_drop = dropped.iter().position(|d| *d).unwrap();
+    assert!(dropped[first_drop..].iter().all(|d| *d));
+    assert!(dropped[..first_drop].iter().all(|d| !*d));
+}
+
+/// with no losses in the good state and only losses in the bad one, a fragment is dropped exactly
+/// when the link is in the bad state
+#[test_log::test]
+fn gilbert_elliott_state() {
+    let mut model = GilbertElliott::new(0.1, 0.1, 0.0, 1.0).unwrap();
+    let mut rng = StdRng::seed_from_u64(3);
+    let fragment = default_fragment(0, 1);
+    for _ in 0..1000 {
+        let dropped = model.should_drop(&fragment, &mut rng);
+        assert_eq!(dropped, model.is_bad());
+    }
+}
+
+#[test]
+fn invalid_probabilities() {
+    assert!(GilbertElliott::new(1.5, 0.1, 0.0, 1.0).is_err());
+    assert!(GilbertElliott::new(0.1, 0.1, -0.1, 1.0).is_err());
+    assert!(Bernoulli::new(2.0).is_err());
+}
+
+/// `DroneCommand::SetPacketDropRate` replaces a custom drop model with a flat pdr
+#[test_log::test]
+fn set_pdr_replaces_model() {
+    let (event_send, _event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, _r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+
+    // would drop every fragment
+    let my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_drop_model(EveryNth::new(1));
+    let _handle = start_drone_thread(my_drone);
+
+    try_send_command(&command_send, DroneCommand::SetPacketDropRate(0.0));
+
+    try_send_packet(
+        &packet_send,
+        PacketBuilder::new_fragment(vec![0, 1, 2]).build(),
+    );
+    let expected = PacketBuilder::new_fragment(vec![0, 1, 2])
+        .hop_index(2)
+        .build();
+    expect_one_packet(&r2, &expected);
+}
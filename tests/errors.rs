@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use common::{create_channels, packetbuilder::PacketBuilder};
+use null_pointer_drone::{DroneError, MyDrone};
+use wg_2024::{
+    drone::Drone,
+    packet::{NodeType, PacketType},
+};
+
+pub mod common;
+
+/// malformed packets passed directly to `process_packet` are reported as errors instead of
+/// panicking
+#[test_log::test]
+fn process_packet_returns_errors() {
+    let (event_send, _event_recv, _command_send, command_recv, _packet_send, packet_recv) =
+        create_channels();
+
+    let mut my_drone = MyDrone::new(
+        1,
+        event_send,
+        command_recv,
+        packet_recv,
+        HashMap::new(),
+        0.0,
+    );
+
+    let p = PacketBuilder::new_fragment(vec![]).build();
+    let res = my_drone.process_packet(p);
+    assert!(matches!(res, Err(DroneError::EmptyRoutingHeader(_))));
+
+    let p = PacketBuilder::new_fragment(vec![0, 1]).hop_index(2).build();
+    let res = my_drone.process_packet(p);
+    assert!(matches!(res, Err(DroneError::HopIndexOutOfBounds(_))));
+
+    let p = PacketBuilder::new_fragment(vec![1, 2]).hop_index(0).build();
+    let res = my_drone.process_packet(p);
+    assert!(matches!(res, Err(DroneError::HopIndexZero(_))));
+
+    let mut p = PacketBuilder::new_floodreq(vec![(0, NodeType::Client)]).build();
+    if let PacketType::FloodRequest(flood_request) = &mut p.pack_type {
+        flood_request.path_trace.clear();
+    }
+    let res = my_drone.process_packet(p);
+    assert!(matches!(
+        res,
+        Err(DroneError::MissingPathTrace {
+            flood_id: 0,
+            initiator_id: 0
+        })
+    ));
+}
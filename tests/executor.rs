@@ -0,0 +1,77 @@
+use std::{collections::HashMap, num::NonZeroUsize, time::Duration};
+
+use common::{chain::Chain, create_channels, packetbuilder::PacketBuilder};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::{DroneExecutor, MyDrone};
+use wg_2024::{controller::DroneCommand, network::NodeId, packet::Packet};
+
+pub mod common;
+
+const CHAIN_LENGTH: NodeId = 200;
+
+/// topology: 0-1-2-...-200-201, the drones 1 to 200 run on 2 worker threads
+#[test_log::test]
+fn many_drones_on_few_threads() {
+    let mut chain = Chain::new(CHAIN_LENGTH);
+    let drones = std::mem::take(&mut chain.drones);
+    let executor = DroneExecutor::spawn(drones, NonZeroUsize::new(2).unwrap()).unwrap();
+
+    chain.send_fragment();
+    let received = chain
+        .last_node()
+        .recv_timeout(Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(received, chain.delivered_fragment());
+
+    chain.crash();
+    let exits = executor.join();
+    assert_eq!(exits.len(), usize::from(CHAIN_LENGTH));
+    assert!(exits.values().all(Result::is_ok));
+}
+
+/// a drone panicking stops alone, the others of its worker keep running
+#[test_log::test]
+fn panic_stops_only_one_drone() {
+    let (event_send, _event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+    let (s2, r2) = unbounded::<Packet>();
+    let healthy = MyDrone::builder(
+        1,
+        event_send.clone(),
+        command_recv,
+        packet_recv,
+        HashMap::new(),
+    )
+    .neighbor(2, s2)
+    .build()
+    .unwrap();
+    // the controller of this drone is gone, which is fatal with the default fault policy
+    let (_, orphan_command_recv) = unbounded::<DroneCommand>();
+    let (_orphan_packet_send, orphan_packet_recv) = unbounded::<Packet>();
+    let orphan = MyDrone::builder(
+        3,
+        event_send,
+        orphan_command_recv,
+        orphan_packet_recv,
+        HashMap::new(),
+    )
+    .build()
+    .unwrap();
+    let executor = DroneExecutor::spawn([healthy, orphan], NonZeroUsize::new(1).unwrap()).unwrap();
+
+    packet_send
+        .send(PacketBuilder::new_fragment(vec![0, 1, 2]).build())
+        .unwrap();
+    assert_eq!(
+        r2.recv_timeout(Duration::from_secs(1)).unwrap(),
+        PacketBuilder::new_fragment(vec![0, 1, 2])
+            .hop_index(2)
+            .build()
+    );
+
+    command_send.send(DroneCommand::Crash).unwrap();
+    drop(packet_send);
+    let exits = executor.join();
+    assert!(exits[&1].is_ok());
+    assert!(exits[&3].is_err());
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use common::{
+    create_channels,
+    expect::{
+        expect_no_event, expect_no_packet, expect_one_event, expect_one_packet, expect_panic,
+        try_send_command, try_send_packet,
+    },
+    packetbuilder::PacketBuilder,
+    start_drone_thread,
+};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::{ErrorClass, FaultAction, FaultPolicy, MyDrone};
+use wg_2024::{
+    controller::{DroneCommand, DroneEvent},
+    drone::Drone,
+    packet::{NackType, Packet},
+};
+
+pub mod common;
+
+/// sends 0 -> (1) -> 2 and checks that the drone forwards it, used to make sure the drone is still
+/// working after an error
+fn expect_forwarding(
+    packet_send: &crossbeam_channel::Sender<Packet>,
+    r2: &crossbeam_channel::Receiver<Packet>,
+    event_recv: &crossbeam_channel::Receiver<DroneEvent>,
+) {
+    let p = PacketBuilder::new_fragment(vec![0, 1, 2]).build();
+    try_send_packet(packet_send, p);
+    let expected = PacketBuilder::new_fragment(vec![0, 1, 2])
+        .hop_index(2)
+        .build();
+    expect_one_packet(r2, &expected);
+    expect_one_event(event_recv, &DroneEvent::PacketSent(expected));
+}
+
+/// malformed routing headers and invalid commands get ignored
+#[test_log::test]
+fn drop_and_log() {
+    let (event_send, event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+
+    let my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_fault_policy(FaultPolicy::uniform(FaultAction::DropAndLog));
+    let _handle = start_drone_thread(my_drone);
+
+    let malformed = [
+        PacketBuilder::new_fragment(vec![]).build(),
+        PacketBuilder::new_fragment(vec![0, 1]).hop_index(2).build(),
+        PacketBuilder::new_fragment(vec![0, 1, 2])
+            .hop_index(0)
+            .build(),
+    ];
+    for p in malformed {
+        try_send_packet(&packet_send, p);
+        expect_no_packet(&r0);
+        expect_no_packet(&r2);
+        expect_no_event(&event_recv);
+    }
+
+    try_send_command(&command_send, DroneCommand::RemoveSender(7));
+    try_send_command(&command_send, DroneCommand::SetPacketDropRate(3.0));
+
+    expect_forwarding(&packet_send, &r2, &event_recv);
+}
+
+/// the drone keeps forwarding packets after the simulation controller drops its command sender
+#[test_log::test]
+fn drop_and_log_dropped_command_sender() {
+    let (event_send, event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s2, r2) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(2, s2);
+
+    let my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_fault_policy(FaultPolicy::uniform(FaultAction::DropAndLog));
+    let _handle = start_drone_thread(my_drone);
+
+    drop(command_send);
+
+    expect_forwarding(&packet_send, &r2, &event_recv);
+}
+
+/// the drone stops without panicking when all the senders to its packet receiver are dropped
+#[test_log::test]
+fn drop_and_log_dropped_packet_sender() {
+    let (event_send, event_recv, _command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let my_drone = MyDrone::new(
+        1,
+        event_send,
+        command_recv,
+        packet_recv,
+        HashMap::new(),
+        0.0,
+    )
+    .with_fault_policy(FaultPolicy::uniform(FaultAction::DropAndLog));
+    let handle = start_drone_thread(my_drone);
+
+    drop(packet_send);
+
+    assert!(handle.join().is_ok());
+    expect_no_event(&event_recv);
+}
+
+/// topology: 0-1-2
+/// packets that reached drone 1 with a wrong `hop_index` are answered with an
+/// `UnexpectedRecipient` nack
+#[test_log::test]
+fn nack_malformed_routing_header() {
+    let (event_send, event_recv, _command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+
+    let my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_fault_policy(FaultPolicy::uniform(FaultAction::Nack));
+    let _handle = start_drone_thread(my_drone);
+
+    let expected = PacketBuilder::new_nack(vec![1, 0], NackType::UnexpectedRecipient(1)).build();
+
+    let malformed = [
+        PacketBuilder::new_fragment(vec![0, 1]).hop_index(2).build(),
+        PacketBuilder::new_fragment(vec![0, 1, 2])
+            .hop_index(0)
+            .build(),
+    ];
+    for p in malformed {
+        try_send_packet(&packet_send, p);
+        expect_one_packet(&r0, &expected);
+        expect_no_packet(&r2);
+        expect_one_event(&event_recv, &DroneEvent::PacketSent(expected.clone()));
+    }
+
+    // no route back, falls back to dropping the packet
+    try_send_packet(&packet_send, PacketBuilder::new_fragment(vec![]).build());
+    expect_no_packet(&r0);
+    expect_no_event(&event_recv);
+
+    expect_forwarding(&packet_send, &r2, &event_recv);
+}
+
+/// topology: 0-1-2
+/// sends 0 -> (1) -> 2 after the receiver of 2 has been dropped, expects an `ErrorInRouting` nack
+#[test_log::test]
+fn nack_send_failure() {
+    let (event_send, event_recv, _command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+
+    let my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_fault_policy(FaultPolicy::uniform(FaultAction::Nack));
+    let _handle = start_drone_thread(my_drone);
+
+    drop(r2);
+    try_send_packet(
+        &packet_send,
+        PacketBuilder::new_fragment(vec![0, 1, 2]).build(),
+    );
+
+    let expected = PacketBuilder::new_nack(vec![1, 0], NackType::ErrorInRouting(2)).build();
+    expect_one_packet(&r0, &expected);
+    expect_one_event(&event_recv, &DroneEvent::PacketSent(expected));
+}
+
+/// the policy is chosen per error class: malformed headers are nacked but invalid commands still
+/// make the drone panic
+#[test_log::test]
+fn per_class_policy() {
+    let (event_send, event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, r0) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+
+    let policy = FaultPolicy::strict().with(ErrorClass::MalformedRoutingHeader, FaultAction::Nack);
+    let my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_fault_policy(policy);
+    let handle = start_drone_thread(my_drone);
+
+    try_send_packet(
+        &packet_send,
+        PacketBuilder::new_fragment(vec![0, 1]).hop_index(2).build(),
+    );
+    let expected = PacketBuilder::new_nack(vec![1, 0], NackType::UnexpectedRecipient(1)).build();
+    expect_one_packet(&r0, &expected);
+    expect_one_event(&event_recv, &DroneEvent::PacketSent(expected));
+
+    try_send_command(&command_send, DroneCommand::RemoveSender(7));
+    expect_panic(handle, "Cannot remove channel to 7: it does not exist");
+}
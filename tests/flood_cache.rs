@@ -0,0 +1,71 @@
+use std::{num::NonZeroUsize, time::Duration};
+
+use common::{line::Line, packetbuilder::PacketBuilder};
+use null_pointer_drone::MockClock;
+use wg_2024::packet::{NodeType, Packet};
+
+pub mod common;
+
+/// the flood `flood_id` of client 0
+fn flood(flood_id: u64) -> Packet {
+    PacketBuilder::new_floodreq_with_opts(vec![(0, NodeType::Client)], flood_id).build()
+}
+
+fn line_with_flood_cache(capacity: usize, ttl: Option<Duration>, clock: MockClock) -> Line {
+    Line::new(|drone| {
+        drone
+            .with_clock(clock)
+            .with_flood_cache(NonZeroUsize::new(capacity).unwrap(), ttl)
+    })
+}
+
+#[test_log::test]
+fn evicted_flood_is_forwarded_again() {
+    let mut line = line_with_flood_cache(1, None, MockClock::new());
+
+    assert!(line.is_forwarded(flood(1)));
+    assert!(!line.is_forwarded(flood(1)));
+    assert_eq!(line.drone.flood_cache_evictions(), 0);
+
+    assert!(line.is_forwarded(flood(2)));
+    assert_eq!(line.drone.flood_cache_evictions(), 1);
+    assert_eq!(line.drone.flood_cache_len(), 1);
+
+    assert!(line.is_forwarded(flood(1)));
+    assert_eq!(line.drone.flood_cache_evictions(), 2);
+}
+
+#[test_log::test]
+fn least_recently_seen_flood_is_evicted() {
+    let mut line = line_with_flood_cache(2, None, MockClock::new());
+
+    assert!(line.is_forwarded(flood(1)));
+    assert!(line.is_forwarded(flood(2)));
+    // seeing flood 1 again makes flood 2 the least recently seen
+    assert!(!line.is_forwarded(flood(1)));
+    assert!(line.is_forwarded(flood(3)));
+    assert_eq!(line.drone.flood_cache_evictions(), 1);
+
+    assert!(!line.is_forwarded(flood(1)));
+    assert!(line.is_forwarded(flood(2)));
+}
+
+#[test_log::test]
+fn expired_flood_is_forwarded_again() {
+    let clock = MockClock::new();
+    let mut line = line_with_flood_cache(16, Some(Duration::from_millis(200)), clock.clone());
+
+    assert!(line.is_forwarded(flood(1)));
+    assert!(!line.is_forwarded(flood(1)));
+
+    // seeing the flood again keeps it in the cache
+    clock.advance(Duration::from_millis(150));
+    assert!(!line.is_forwarded(flood(1)));
+    clock.advance(Duration::from_millis(150));
+    assert!(!line.is_forwarded(flood(1)));
+
+    clock.advance(Duration::from_millis(200));
+    assert!(line.is_forwarded(flood(1)));
+    assert_eq!(line.drone.flood_cache_evictions(), 1);
+    assert_eq!(line.drone.flood_cache_len(), 1);
+}
@@ -0,0 +1,66 @@
+use std::{num::NonZeroU32, time::Duration};
+
+use common::{line::Line, packetbuilder::PacketBuilder};
+use null_pointer_drone::{DroneError, FloodRateLimit, MockClock};
+use wg_2024::{
+    network::NodeId,
+    packet::{NodeType, Packet},
+};
+
+pub mod common;
+
+/// the flood `flood_id` of `initiator_id`, coming from drone 0
+fn flood(initiator_id: NodeId, flood_id: u64) -> Packet {
+    let path_trace = vec![(initiator_id, NodeType::Client), (0, NodeType::Drone)];
+    PacketBuilder::new_floodreq_with_opts(path_trace, flood_id).build()
+}
+
+fn line_with_flood_rate_limit(requests_per_second: f64, burst: u32, clock: MockClock) -> Line {
+    let limit = FloodRateLimit::new(requests_per_second, NonZeroU32::new(burst).unwrap()).unwrap();
+    Line::new(|drone| drone.with_clock(clock).with_flood_rate_limit(limit))
+}
+
+#[test_log::test]
+fn floods_over_the_limit_are_answered() {
+    let mut line = line_with_flood_rate_limit(0.001, 2, MockClock::new());
+
+    assert!(line.is_forwarded(flood(100, 1)));
+    assert!(line.is_forwarded(flood(100, 2)));
+    assert!(!line.is_forwarded(flood(100, 3)));
+    assert!(!line.is_forwarded(flood(100, 4)));
+    assert_eq!(line.drone.rate_limited_flood_requests(), 2);
+
+    // every initiator has its own bucket
+    assert!(line.is_forwarded(flood(101, 1)));
+
+    // floods already seen are answered without taking tokens
+    assert!(!line.is_forwarded(flood(101, 1)));
+    assert!(line.is_forwarded(flood(101, 2)));
+    assert_eq!(line.drone.rate_limited_flood_requests(), 2);
+}
+
+#[test_log::test]
+fn tokens_are_refilled_over_time() {
+    let clock = MockClock::new();
+    let mut line = line_with_flood_rate_limit(10.0, 1, clock.clone());
+
+    assert!(line.is_forwarded(flood(100, 1)));
+    assert!(!line.is_forwarded(flood(100, 2)));
+
+    clock.advance(Duration::from_millis(50));
+    assert!(!line.is_forwarded(flood(100, 3)));
+    clock.advance(Duration::from_millis(50));
+    assert!(line.is_forwarded(flood(100, 4)));
+    assert_eq!(line.drone.rate_limited_flood_requests(), 2);
+}
+
+#[test]
+fn invalid_flood_rate_limits() {
+    let burst = NonZeroU32::new(1).unwrap();
+    for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(
+            FloodRateLimit::new(rate, burst),
+            Err(DroneError::InvalidFloodRateLimit(_))
+        ));
+    }
+}
@@ -0,0 +1,188 @@
+use std::{collections::HashMap, time::Duration};
+
+use common::{
+    create_channels,
+    expect::{expect_no_packet, expect_one_packet, try_send_packet},
+    packets::{forwarded, fragment},
+    start_drone_thread,
+};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use null_pointer_drone::{
+    Clock, DelayModel, DroneError, LinkDelay, MockClock, MyDrone, SystemClock,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use wg_2024::{
+    controller::{DroneCommand, DroneEvent},
+    drone::Drone,
+    packet::Packet,
+};
+
+pub mod common;
+
+/// the command sender and the event receiver are kept so that the drone keeps running
+struct DelayedDrone {
+    packet_send: Sender<Packet>,
+    _command_send: Sender<DroneCommand>,
+    _event_recv: Receiver<DroneEvent>,
+    r2: Receiver<Packet>,
+    r3: Receiver<Packet>,
+}
+
+/// topology: 0-1-2 and 1-3, only the link 1-2 has a delay
+fn start_delayed_drone(delay: LinkDelay, clock: impl Clock + 'static) -> DelayedDrone {
+    let (event_send, event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, _r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let (s3, r3) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+    senders.insert(3, s3);
+
+    let my_drone = MyDrone::builder(1, event_send, command_recv, packet_recv, senders)
+        .seed(7)
+        .clock(clock)
+        .neighbor_delay(2, delay)
+        .build()
+        .unwrap();
+    let _handle = start_drone_thread(my_drone);
+    DelayedDrone {
+        packet_send,
+        _command_send: command_send,
+        _event_recv: event_recv,
+        r2,
+        r3,
+    }
+}
+
+#[test_log::test]
+fn fixed_delay() {
+    let clock = MockClock::new();
+    let delay = LinkDelay::new(DelayModel::Fixed(Duration::from_millis(150)));
+    let drone = start_delayed_drone(delay, clock.clone());
+
+    try_send_packet(&drone.packet_send, fragment(vec![0, 1, 2], 1));
+    // the other links are not delayed, and the drone keeps working while the packet waits
+    try_send_packet(&drone.packet_send, fragment(vec![0, 1, 3], 2));
+    expect_one_packet(&drone.r3, &forwarded(vec![0, 1, 3], 2));
+    expect_no_packet(&drone.r2);
+
+    clock.advance(Duration::from_millis(149));
+    expect_no_packet(&drone.r2);
+    clock.advance(Duration::from_millis(1));
+    expect_one_packet(&drone.r2, &forwarded(vec![0, 1, 2], 1));
+}
+
+fn received_session_ids(r2: &Receiver<Packet>, n: usize) -> Vec<u64> {
+    (0..n)
+        .map(|_| r2.recv_timeout(Duration::from_secs(1)).unwrap().session_id)
+        .collect()
+}
+
+#[test_log::test]
+fn jitter_preserves_order() {
+    let jitter = DelayModel::uniform(Duration::ZERO, Duration::from_millis(50)).unwrap();
+    let drone = start_delayed_drone(LinkDelay::new(jitter), SystemClock);
+
+    for session_id in 0..20 {
+        try_send_packet(&drone.packet_send, fragment(vec![0, 1, 2], session_id));
+    }
+    assert_eq!(
+        received_session_ids(&drone.r2, 20),
+        (0..20).collect::<Vec<_>>()
+    );
+}
+
+#[test_log::test]
+fn jitter_can_reorder() {
+    let jitter = DelayModel::uniform(Duration::ZERO, Duration::from_millis(50)).unwrap();
+    let drone = start_delayed_drone(LinkDelay::new(jitter).allow_reordering(), SystemClock);
+
+    for session_id in 0..20 {
+        try_send_packet(&drone.packet_send, fragment(vec![0, 1, 2], session_id));
+    }
+    let mut received = received_session_ids(&drone.r2, 20);
+    assert_ne!(received, (0..20).collect::<Vec<_>>());
+    received.sort_unstable();
+    assert_eq!(received, (0..20).collect::<Vec<_>>());
+}
+
+#[test]
+fn delay_models() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mean = Duration::from_millis(30);
+
+    assert_eq!(
+        DelayModel::normal(mean, Duration::ZERO)
+            .unwrap()
+            .sample(&mut rng)
+            .unwrap(),
+        mean
+    );
+
+    let normal = DelayModel::normal(mean, Duration::from_millis(100)).unwrap();
+    let uniform =
+        DelayModel::uniform(Duration::from_millis(10), Duration::from_millis(20)).unwrap();
+    for _ in 0..1000 {
+        // negative delays are clamped to zero, so sampling must not panic
+        normal.sample(&mut rng).unwrap();
+        let delay = uniform.sample(&mut rng).unwrap();
+        assert!((Duration::from_millis(10)..=Duration::from_millis(20)).contains(&delay));
+    }
+
+    assert!(matches!(
+        DelayModel::uniform(Duration::from_millis(20), Duration::from_millis(10)),
+        Err(DroneError::InvalidDelay(_))
+    ));
+    assert!(matches!(
+        DelayModel::normal(Duration::MAX, Duration::ZERO),
+        Err(DroneError::InvalidDelay(_))
+    ));
+    assert!(matches!(
+        DelayModel::normal(Duration::ZERO, Duration::MAX / 2),
+        Err(DroneError::InvalidDelay(_))
+    ));
+    // the samples close to the maximum do not fit in a Duration once converted to seconds
+    let huge = DelayModel::uniform(Duration::MAX, Duration::MAX).unwrap();
+    assert!(matches!(
+        huge.sample(&mut rng),
+        Err(DroneError::InvalidDelay(_))
+    ));
+}
+
+#[test]
+fn invalid_delays() {
+    let (event_send, _event_recv, _command_send, command_recv, _packet_send, packet_recv) =
+        create_channels();
+    let (s2, _r2) = unbounded::<Packet>();
+    let senders = HashMap::from([(2, s2)]);
+    let mut my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0);
+
+    // the fields of the variant are public, so the check of `DelayModel::uniform` can be skipped
+    let backwards = DelayModel::Uniform {
+        min: Duration::from_millis(20),
+        max: Duration::from_millis(10),
+    };
+    assert!(matches!(
+        my_drone.set_neighbor_delay(2, Some(LinkDelay::new(backwards))),
+        Err(DroneError::InvalidDelay(_))
+    ));
+
+    let huge_mean = DelayModel::Normal {
+        mean: Duration::MAX,
+        std_dev: Duration::ZERO,
+    };
+    assert!(matches!(
+        my_drone.set_neighbor_delay(2, Some(LinkDelay::new(huge_mean))),
+        Err(DroneError::InvalidDelay(_))
+    ));
+
+    let forever = LinkDelay::new(DelayModel::Fixed(Duration::MAX));
+    my_drone.set_neighbor_delay(2, Some(forever)).unwrap();
+    assert!(matches!(
+        my_drone.process_packet(fragment(vec![0, 1, 2], 1)),
+        Err(DroneError::InvalidDelay(_))
+    ));
+}
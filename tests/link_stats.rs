@@ -0,0 +1,92 @@
+use std::{collections::HashMap, time::Duration};
+
+use common::{
+    create_channels,
+    expect::{try_send_command, try_send_packet},
+    packetbuilder::PacketBuilder,
+    start_drone_thread, RECV_WAIT_TIME,
+};
+use crossbeam_channel::{unbounded, Receiver};
+use null_pointer_drone::{FaultAction, FaultPolicy, LinkStats, MyDrone};
+use wg_2024::{
+    controller::{DroneCommand, DroneEvent},
+    drone::Drone,
+    packet::Packet,
+};
+
+pub mod common;
+
+fn wait_event(event_recv: &Receiver<DroneEvent>) {
+    event_recv
+        .recv_timeout(Duration::from_millis(RECV_WAIT_TIME))
+        .unwrap();
+}
+
+/// topology: 0-1-2 and 1-3, the receiver of 3 is dropped
+#[test_log::test]
+fn link_stats() {
+    let (event_send, event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, _r0) = unbounded::<Packet>();
+    let (s2, _r2) = unbounded::<Packet>();
+    let (s3, r3) = unbounded::<Packet>();
+    drop(r3);
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+    senders.insert(3, s3);
+
+    let my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_fault_policy(FaultPolicy::uniform(FaultAction::DropAndLog));
+    let stats = my_drone.stats_handle();
+    let _handle = start_drone_thread(my_drone);
+
+    let to_2 = PacketBuilder::new_fragment(vec![0, 1, 2]).build();
+    let to_3 = PacketBuilder::new_fragment(vec![0, 1, 3]).build();
+    let ack_to_2 = PacketBuilder::new_ack(vec![0, 1, 2]).build();
+
+    try_send_packet(&packet_send, to_2.clone());
+    try_send_packet(&packet_send, ack_to_2);
+    wait_event(&event_recv);
+    wait_event(&event_recv);
+    assert_eq!(
+        stats.link(2),
+        Some(LinkStats {
+            packets: 2,
+            bytes: 128,
+            ..LinkStats::default()
+        })
+    );
+
+    try_send_packet(&packet_send, to_3);
+    try_send_packet(&packet_send, to_2.clone());
+    wait_event(&event_recv);
+    assert_eq!(
+        stats.link(3),
+        Some(LinkStats {
+            failures: 1,
+            ..LinkStats::default()
+        })
+    );
+    assert_eq!(stats.link(0), Some(LinkStats::default()));
+
+    // the counters of a link are removed with it, and start from zero when it is added again
+    try_send_command(&command_send, DroneCommand::RemoveSender(2));
+    try_send_packet(&packet_send, to_2.clone());
+    wait_event(&event_recv);
+    assert_eq!(stats.link(2), None);
+
+    let (s2, _r2) = unbounded::<Packet>();
+    try_send_command(&command_send, DroneCommand::AddSender(2, s2));
+    try_send_packet(&packet_send, to_2);
+    wait_event(&event_recv);
+    assert_eq!(
+        stats.link(2),
+        Some(LinkStats {
+            packets: 1,
+            bytes: 128,
+            ..LinkStats::default()
+        })
+    );
+}
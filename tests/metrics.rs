@@ -0,0 +1,121 @@
+#![cfg(feature = "prometheus")]
+
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, Read, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+use common::{
+    create_channels, expect::try_send_packet, packetbuilder::PacketBuilder, start_drone_thread,
+    RECV_WAIT_TIME,
+};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::{
+    metrics::{render, MetricsServer},
+    DroneStats, MyDrone,
+};
+use wg_2024::{drone::Drone, packet::Packet};
+
+pub mod common;
+
+fn get(server: &MetricsServer, path: &str) -> String {
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: localhost\r\nAccept: text/plain\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn render_empty_stats() {
+    let text = render(&[(7, DroneStats::default())]);
+    assert!(text.contains("# TYPE npd_packets_received_total counter\n"));
+    assert!(text.contains("npd_packets_received_total{drone=\"7\",type=\"fragment\"} 0\n"));
+    assert!(text.contains("npd_nacks_total{drone=\"7\",nack_type=\"dropped\"} 0\n"));
+    assert!(text.contains("npd_neighbors{drone=\"7\"} 0\n"));
+    assert!(text.contains("npd_state{drone=\"7\",state=\"working\"} 1\n"));
+    assert!(text.contains("npd_state{drone=\"7\",state=\"crashing\"} 0\n"));
+    // every sample line is `<name>{<labels>} <value>`
+    for line in text.lines().filter(|line| !line.starts_with('#')) {
+        let (series, value) = line.rsplit_once(' ').unwrap();
+        assert!(series.ends_with('}'), "{line}");
+        assert!(value.parse::<f64>().is_ok(), "{line}");
+    }
+}
+
+/// topology: 0-1-2 and 0-3-2, the drones 1 and 3 are served by the same server
+#[test_log::test]
+fn serve_metrics() {
+    let (event_send, event_recv, _command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+    let (s0, _r0) = unbounded::<Packet>();
+    let (s2, _r2) = unbounded::<Packet>();
+    let senders = HashMap::from([(0, s0.clone()), (2, s2.clone())]);
+    let drone_1 = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 1.0);
+
+    let (event_send, _event_recv_3, _command_send_3, command_recv, _packet_send_3, packet_recv) =
+        create_channels();
+    let senders = HashMap::from([(0, s0), (2, s2)]);
+    let drone_3 = MyDrone::new(3, event_send, command_recv, packet_recv, senders, 0.25);
+
+    let server = MetricsServer::start(
+        "127.0.0.1:0",
+        [(1, drone_1.stats_handle()), (3, drone_3.stats_handle())],
+    )
+    .unwrap();
+    let _handle = start_drone_thread(drone_1);
+    let _handle_3 = start_drone_thread(drone_3);
+
+    // dropped, and nacked
+    try_send_packet(
+        &packet_send,
+        PacketBuilder::new_fragment(vec![0, 1, 2]).build(),
+    );
+    for _ in 0..2 {
+        event_recv
+            .recv_timeout(Duration::from_millis(RECV_WAIT_TIME))
+            .unwrap();
+    }
+
+    let response = get(&server, "/metrics");
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(head.contains("Content-Type: text/plain; version=0.0.4"));
+    for line in [
+        "npd_packets_received_total{drone=\"1\",type=\"fragment\"} 1",
+        "npd_packets_dropped_total{drone=\"1\",type=\"fragment\"} 1",
+        "npd_packets_forwarded_total{drone=\"1\",type=\"nack\"} 1",
+        "npd_nacks_total{drone=\"1\",nack_type=\"dropped\"} 1",
+        "npd_link_drops_total{drone=\"1\",neighbor=\"2\"} 1",
+        "npd_neighbors{drone=\"1\"} 2",
+        "npd_pdr{drone=\"1\"} 1",
+        "npd_pdr{drone=\"3\"} 0.25",
+        "npd_packets_received_total{drone=\"3\",type=\"fragment\"} 0",
+    ] {
+        assert!(body.lines().any(|l| l == line), "missing {line} in\n{body}");
+    }
+
+    assert!(get(&server, "/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
+
+#[test]
+fn idle_client_does_not_block_scrapes() {
+    let server = MetricsServer::start("127.0.0.1:0", []).unwrap();
+    let _idle = TcpStream::connect(server.local_addr()).unwrap();
+
+    let start = Instant::now();
+    assert!(get(&server, "/metrics").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(start.elapsed() < Duration::from_millis(400));
+}
+
+#[test]
+fn only_loopback_addresses() {
+    let error = MetricsServer::start("0.0.0.0:0", []).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
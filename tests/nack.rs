@@ -1,5 +1,6 @@
 use common::{
     expect::{expect_one_packet, try_send_packet},
+    line::Line,
     packetbuilder::PacketBuilder,
 };
 use crossbeam_channel::unbounded;
@@ -138,3 +139,17 @@ fn nack_when_there_are_no_neighbours() {
 
     expect_one_packet(&neighbour_receive_1, &expected);
 }
+
+/// the nack starts from the position of the drone in the route, which is not its id
+#[test]
+fn error_in_routing_ids_are_not_positions() {
+    let mut line = Line::new(|drone| drone);
+
+    let packet = PacketBuilder::new_fragment(vec![7, 0, 1, 9])
+        .hop_index(2)
+        .build();
+    line.drone.process_packet(packet).unwrap();
+
+    let expected = PacketBuilder::new_nack(vec![1, 0, 7], NackType::ErrorInRouting(9)).build();
+    assert_eq!(line.r0.try_recv().unwrap(), expected);
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use common::{
+    create_channels,
+    expect::{expect_no_packet, expect_one_packet, try_send_command, try_send_packet},
+    packetbuilder::PacketBuilder,
+    start_drone_thread,
+};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::{ControlCommand, DroneError, MyDrone};
+use wg_2024::{
+    controller::DroneCommand,
+    drone::Drone,
+    packet::{NackType, Packet},
+};
+
+pub mod common;
+
+/// topology: 0-1-2 and 1-3
+/// only the link 1-2 is lossy, then at runtime the link 1-3 becomes lossy and the link 1-2 falls
+/// back to the pdr of the drone
+#[test_log::test]
+fn per_neighbor_pdr() {
+    let (event_send, _event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let (s3, r3) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+    senders.insert(3, s3);
+
+    let mut my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0);
+    my_drone.set_neighbor_pdr(2, Some(1.0)).unwrap();
+    let control_send = my_drone.control_sender();
+    let _handle = start_drone_thread(my_drone);
+
+    let to_2 = PacketBuilder::new_fragment(vec![0, 1, 2]).build();
+    let to_3 = PacketBuilder::new_fragment(vec![0, 1, 3]).build();
+    let dropped = PacketBuilder::new_nack(vec![1, 0], NackType::Dropped).build();
+    let forwarded_to_2 = PacketBuilder::new_fragment(vec![0, 1, 2])
+        .hop_index(2)
+        .build();
+    let forwarded_to_3 = PacketBuilder::new_fragment(vec![0, 1, 3])
+        .hop_index(2)
+        .build();
+
+    try_send_packet(&packet_send, to_2.clone());
+    expect_one_packet(&r0, &dropped);
+    expect_no_packet(&r2);
+
+    try_send_packet(&packet_send, to_3.clone());
+    expect_one_packet(&r3, &forwarded_to_3);
+    expect_no_packet(&r0);
+
+    control_send
+        .send(ControlCommand::SetNeighborPdr(3, Some(1.0)))
+        .unwrap();
+    control_send
+        .send(ControlCommand::SetNeighborPdr(2, None))
+        .unwrap();
+
+    try_send_packet(&packet_send, to_2.clone());
+    expect_one_packet(&r2, &forwarded_to_2);
+    expect_no_packet(&r0);
+
+    try_send_packet(&packet_send, to_3);
+    expect_one_packet(&r0, &dropped);
+    expect_no_packet(&r3);
+
+    // the pdr of a link is forgotten when the link is removed and added again
+    control_send
+        .send(ControlCommand::SetNeighborPdr(2, Some(1.0)))
+        .unwrap();
+    try_send_packet(&packet_send, to_2.clone());
+    expect_one_packet(&r0, &dropped);
+
+    let (s2, r2) = unbounded::<Packet>();
+    try_send_command(&command_send, DroneCommand::RemoveSender(2));
+    try_send_command(&command_send, DroneCommand::AddSender(2, s2));
+
+    try_send_packet(&packet_send, to_2);
+    expect_one_packet(&r2, &forwarded_to_2);
+    expect_no_packet(&r0);
+}
+
+#[test_log::test]
+fn invalid_neighbor_pdr() {
+    let (event_send, _event_recv, _command_send, command_recv, _packet_send, packet_recv) =
+        create_channels();
+
+    let (s2, _r2) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(2, s2);
+
+    let mut my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0);
+
+    assert!(matches!(
+        my_drone.set_neighbor_pdr(3, Some(0.5)),
+        Err(DroneError::UnknownNeighbor(3))
+    ));
+    assert!(matches!(
+        my_drone.set_neighbor_pdr(2, Some(1.5)),
+        Err(DroneError::InvalidPdr(_))
+    ));
+    assert!(my_drone.set_neighbor_pdr(2, None).is_ok());
+}
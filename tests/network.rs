@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use common::packetbuilder::PacketBuilder;
+use null_pointer_drone::{
+    network::{self, NetworkError},
+    TopologyIssue,
+};
+use wg_2024::controller::DroneCommand;
+
+pub mod common;
+
+/// client 10 - drones 1, 2 - server 20, with the drones also connected to each other
+const TOPOLOGY: &str = r"
+[[drone]]
+id = 1
+connected_node_ids = [2, 10, 20]
+pdr = 0.0
+
+[[drone]]
+id = 2
+connected_node_ids = [1, 10, 20]
+pdr = 0.0
+
+[[client]]
+id = 10
+connected_drone_ids = [1, 2]
+
+[[server]]
+id = 20
+connected_drone_ids = [1, 2]
+";
+
+#[test_log::test]
+fn packet_crosses_the_network() {
+    let config = network::parse(TOPOLOGY).unwrap();
+    let (network, threads) = network::start(&config).unwrap();
+
+    let client = &network.clients[&10];
+    client.neighbors[&1]
+        .send(PacketBuilder::new_fragment(vec![10, 1, 20]).build())
+        .unwrap();
+    assert_eq!(
+        network.servers[&20]
+            .packet_recv
+            .recv_timeout(Duration::from_secs(1))
+            .unwrap(),
+        PacketBuilder::new_fragment(vec![10, 1, 20])
+            .hop_index(2)
+            .build()
+    );
+    assert!(network.drones[&1]
+        .event_recv
+        .recv_timeout(Duration::from_secs(1))
+        .is_ok());
+
+    // crash the drones, removing them from their neighbors as the simulation controller would
+    for (id, handle) in &network.drones {
+        handle.command_send.send(DroneCommand::Crash).unwrap();
+        for (neighbor, neighbor_handle) in &network.drones {
+            if neighbor != id {
+                neighbor_handle
+                    .command_send
+                    .send(DroneCommand::RemoveSender(*id))
+                    .unwrap();
+            }
+        }
+    }
+    let network::Network {
+        drones,
+        clients,
+        servers,
+    } = network;
+    drop(clients);
+    drop(servers);
+    let command_senders: Vec<_> = drones
+        .into_values()
+        .map(|handle| (handle.command_send, handle.event_recv))
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    drop(command_senders);
+}
+
+#[test]
+fn invalid_topologies() {
+    // drone 1 forgets drone 2
+    let asymmetric = TOPOLOGY.replacen("[2, 10, 20]", "[10, 20]", 1);
+    assert_eq!(
+        network::validate(&network::parse(&asymmetric).unwrap()),
+        Err(vec![TopologyIssue::AsymmetricLink {
+            node: 2,
+            neighbor: 1
+        }])
+    );
+
+    let self_link = TOPOLOGY.replacen("[2, 10, 20]", "[1, 2, 10, 20]", 1);
+    assert_eq!(
+        network::validate(&network::parse(&self_link).unwrap()),
+        Err(vec![TopologyIssue::SelfLink(1)])
+    );
+
+    let invalid_pdr = TOPOLOGY.replacen("pdr = 0.0", "pdr = 1.5", 1);
+    assert_eq!(
+        network::validate(&network::parse(&invalid_pdr).unwrap()),
+        Err(vec![TopologyIssue::InvalidPdr { drone: 1, pdr: 1.5 }])
+    );
+
+    let isolated = format!("{TOPOLOGY}\n[[client]]\nid = 11\nconnected_drone_ids = []\n");
+    let config = network::parse(&isolated).unwrap();
+    assert_eq!(
+        network::validate(&config),
+        Err(vec![TopologyIssue::Isolated(11)])
+    );
+    assert!(matches!(
+        network::init(&config),
+        Err(NetworkError::Topology(issues)) if issues == [TopologyIssue::Isolated(11)]
+    ));
+
+    assert!(matches!(
+        network::parse("[[drone]]\nid = 1\n"),
+        Err(NetworkError::Parse(_))
+    ));
+}
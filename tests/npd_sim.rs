@@ -0,0 +1,96 @@
+use std::process::Command;
+
+/// client 10 - drones 1, 2 - server 20
+const TOPOLOGY: &str = r"
+[[drone]]
+id = 1
+connected_node_ids = [2, 10, 20]
+pdr = 0.0
+
+[[drone]]
+id = 2
+connected_node_ids = [1, 20]
+pdr = 0.0
+
+[[client]]
+id = 10
+connected_drone_ids = [1]
+
+[[server]]
+id = 20
+connected_drone_ids = [1, 2]
+";
+
+const SCENARIO: &str = r#"
+topology = "topology.toml"
+duration = 0.2
+
+[[step]]
+action = "send"
+from = 10
+hops = [10, 1, 2, 20]
+
+[[expect]]
+node = 20
+receives = "fragment"
+
+[[expect]]
+node = 10
+receives = "nack"
+"#;
+
+#[test]
+fn command_line() {
+    let dir = std::env::temp_dir().join(format!("npd-sim-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let topology = dir.join("topology.toml");
+    std::fs::write(&topology, TOPOLOGY).unwrap();
+    let scenario = dir.join("scenario.toml");
+    std::fs::write(&scenario, SCENARIO).unwrap();
+
+    // every client floods, the flood request reaches both drones
+    let output = Command::new(env!("CARGO_BIN_EXE_npd-sim"))
+        .args(["--duration", "0.2", "--seed", "1", "--format", "json"])
+        .arg(&topology)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("{\"drones\":[{\"id\":1,"), "{stdout}");
+    assert!(stdout.contains("\"flood_request\":1"), "{stdout}");
+    assert!(stdout.ends_with("\"passed\":true}\n"), "{stdout}");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_npd-sim"))
+        .arg("--scenario")
+        .arg(&scenario)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("drone   pdr  received"), "{stdout}");
+    assert!(
+        stdout.contains("PASS node 20 receives a fragment"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("FAIL node 10 receives a nack"), "{stdout}");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_npd-sim"))
+        .args(["--format", "json", "--scenario"])
+        .arg(&scenario)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("{\"expectation\":\"node 20 receives a fragment\",\"passed\":true,"),
+        "{stdout}"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_npd-sim"))
+        .args(["--format", "yaml"])
+        .arg(&topology)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,98 @@
+use std::num::NonZeroUsize;
+
+use common::{
+    expect::{
+        expect_no_packet, expect_one_packet, expect_packet, try_send_command, try_send_packet,
+    },
+    line::Line,
+    packetbuilder::PacketBuilder,
+    packets::{forwarded, fragment},
+};
+use null_pointer_drone::{OverflowPolicy, QueueConfig, StepOutcome};
+use wg_2024::{
+    controller::DroneCommand,
+    packet::{NackType, Packet},
+};
+
+pub mod common;
+
+fn dropped(session_id: u64) -> Packet {
+    PacketBuilder::new_nack(vec![1, 0], NackType::Dropped)
+        .session_id(session_id)
+        .build()
+}
+
+/// topology: 0-1-2, the channel to 2 has room for a single packet and so does the outbound queue
+fn congested_line(overflow: OverflowPolicy) -> Line {
+    Line::bounded(1, |drone| {
+        drone.with_outbound_queue(QueueConfig::new(NonZeroUsize::new(1).unwrap(), overflow))
+    })
+}
+
+#[test_log::test]
+fn drop_tail() {
+    let mut line = congested_line(OverflowPolicy::DropTail);
+
+    for session_id in 1..=3 {
+        line.drone
+            .process_packet(fragment(vec![0, 1, 2], session_id))
+            .unwrap();
+    }
+    expect_one_packet(&line.r0, &dropped(3));
+    assert_eq!(line.drone.stats_handle().link(2).unwrap().drops, 1);
+
+    expect_one_packet(&line.r2, &forwarded(vec![0, 1, 2], 1));
+    line.drone
+        .process_packet(fragment(vec![0, 1, 2], 4))
+        .unwrap();
+    expect_one_packet(&line.r2, &forwarded(vec![0, 1, 2], 2));
+    expect_no_packet(&line.r0);
+}
+
+#[test_log::test]
+fn drop_head() {
+    let mut line = congested_line(OverflowPolicy::DropHead);
+
+    for session_id in 1..=3 {
+        line.drone
+            .process_packet(fragment(vec![0, 1, 2], session_id))
+            .unwrap();
+    }
+    expect_one_packet(&line.r0, &dropped(2));
+
+    expect_one_packet(&line.r2, &forwarded(vec![0, 1, 2], 1));
+    line.drone
+        .process_packet(fragment(vec![0, 1, 2], 4))
+        .unwrap();
+    expect_one_packet(&line.r2, &forwarded(vec![0, 1, 2], 3));
+    expect_no_packet(&line.r0);
+}
+
+/// the drone stops reading packets while the queue to 2 is full, but it keeps processing commands
+#[test_log::test]
+fn block_keeps_processing_commands() {
+    let mut line = congested_line(OverflowPolicy::Block);
+    let stats = line.drone.stats_handle();
+
+    for session_id in 1..=3 {
+        try_send_packet(&line.packet_send, fragment(vec![0, 1, 2], session_id));
+    }
+    // the first fragment fills the channel and the second one the queue
+    assert!(matches!(line.drone.step(), StepOutcome::Packet(_)));
+    assert!(matches!(line.drone.step(), StepOutcome::Packet(_)));
+    assert!(matches!(line.drone.step(), StepOutcome::Timer));
+    assert_eq!(stats.snapshot().received.fragment, 2);
+
+    try_send_command(&line.command_send, DroneCommand::SetPacketDropRate(0.5));
+    assert!(matches!(line.drone.step(), StepOutcome::Command(_)));
+    assert!((stats.snapshot().pdr - 0.5).abs() < f32::EPSILON);
+    try_send_command(&line.command_send, DroneCommand::SetPacketDropRate(0.0));
+    assert!(matches!(line.drone.step(), StepOutcome::Command(_)));
+
+    // every packet taken from the channel makes room for the next one
+    for session_id in 1..=3 {
+        expect_packet(&line.r2, &forwarded(vec![0, 1, 2], session_id));
+        line.drone.step();
+    }
+    expect_no_packet(&line.r0);
+}
@@ -0,0 +1,174 @@
+use std::{collections::HashMap, time::Duration};
+
+use common::{
+    create_channels,
+    expect::{expect_no_packet, expect_one_packet, try_send_command, try_send_packet},
+    line::Line,
+    packetbuilder::PacketBuilder,
+    packets::{forwarded, fragment},
+    start_drone_thread,
+};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::{
+    ControlCommand, DroneError, EveryNth, MockClock, MyDrone, PdrSchedule,
+};
+use wg_2024::{
+    controller::DroneCommand,
+    drone::Drone,
+    packet::{NackType, Packet},
+};
+
+pub mod common;
+
+#[test]
+fn ramp() {
+    let schedule = PdrSchedule::ramp(0.0, 0.5, Duration::from_secs(30)).unwrap();
+    assert!(schedule.pdr_at(Duration::ZERO).abs() < f32::EPSILON);
+    assert!((schedule.pdr_at(Duration::from_secs(15)) - 0.25).abs() < 1e-6);
+    assert!((schedule.pdr_at(Duration::from_secs(30)) - 0.5).abs() < f32::EPSILON);
+    assert!((schedule.pdr_at(Duration::from_secs(60)) - 0.5).abs() < f32::EPSILON);
+}
+
+#[test]
+fn square_wave() {
+    let schedule =
+        PdrSchedule::square_wave(0.1, 1.0, Duration::from_secs(10), Duration::from_secs(2))
+            .unwrap();
+    for (secs, expected) in [
+        (0, 0.1),
+        (9, 0.1),
+        (10, 1.0),
+        (11, 1.0),
+        (12, 0.1),
+        (22, 1.0),
+    ] {
+        let pdr = schedule.pdr_at(Duration::from_secs(secs));
+        assert!(
+            (pdr - expected).abs() < f32::EPSILON,
+            "at {secs}s got {pdr}"
+        );
+    }
+}
+
+#[test]
+fn parse_piecewise() {
+    let schedule = PdrSchedule::parse(
+        "# outage between 10 and 15.5 seconds
+        0    0.05
+
+        10   1.0
+        15.5 0.05",
+    )
+    .unwrap();
+    assert_eq!(
+        schedule,
+        PdrSchedule::Piecewise(vec![
+            (Duration::ZERO, 0.05),
+            (Duration::from_secs(10), 1.0),
+            (Duration::from_millis(15500), 0.05),
+        ])
+    );
+    assert!((schedule.pdr_at(Duration::from_secs(12)) - 1.0).abs() < f32::EPSILON);
+    assert!((schedule.pdr_at(Duration::from_secs(16)) - 0.05).abs() < f32::EPSILON);
+}
+
+#[test]
+fn invalid_schedules() {
+    assert!(matches!(
+        PdrSchedule::parse("0 0.1\n5"),
+        Err(DroneError::InvalidPdrSchedule(_))
+    ));
+    assert!(matches!(
+        PdrSchedule::parse("5 0.1\n0 0.2"),
+        Err(DroneError::InvalidPdrSchedule(_))
+    ));
+    assert!(matches!(
+        PdrSchedule::parse(""),
+        Err(DroneError::InvalidPdrSchedule(_))
+    ));
+    assert!(matches!(
+        PdrSchedule::parse("0 1.2"),
+        Err(DroneError::InvalidPdr(_))
+    ));
+    assert!(matches!(
+        PdrSchedule::from_file("this/file/does/not/exist"),
+        Err(DroneError::InvalidPdrSchedule(_))
+    ));
+}
+
+/// topology: 0-1-2
+/// the link starts working, goes down after 500ms, then the schedule is removed by a
+/// `SetPacketDropRate`
+#[test_log::test]
+fn drone_follows_schedule() {
+    let (event_send, _event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+
+    let schedule = PdrSchedule::piecewise(vec![
+        (Duration::ZERO, 0.0),
+        (Duration::from_millis(500), 1.0),
+    ])
+    .unwrap();
+    let clock = MockClock::new();
+    let my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0)
+        .with_clock(clock.clone())
+        .with_pdr_schedule(schedule)
+        .unwrap();
+    let control_send = my_drone.control_sender();
+    let _handle = start_drone_thread(my_drone);
+
+    let p = PacketBuilder::new_fragment(vec![0, 1, 2]).build();
+    let forwarded = PacketBuilder::new_fragment(vec![0, 1, 2])
+        .hop_index(2)
+        .build();
+    let dropped = PacketBuilder::new_nack(vec![1, 0], NackType::Dropped).build();
+
+    try_send_packet(&packet_send, p.clone());
+    expect_one_packet(&r2, &forwarded);
+
+    clock.advance(Duration::from_millis(500));
+    try_send_packet(&packet_send, p.clone());
+    expect_one_packet(&r0, &dropped);
+    expect_no_packet(&r2);
+
+    try_send_command(&command_send, DroneCommand::SetPacketDropRate(0.0));
+    try_send_packet(&packet_send, p.clone());
+    expect_one_packet(&r2, &forwarded);
+
+    // a schedule installed at runtime starts from its own pdr
+    let schedule = PdrSchedule::ramp(1.0, 1.0, Duration::from_secs(1)).unwrap();
+    control_send
+        .send(ControlCommand::SetPdrSchedule(Some(schedule)))
+        .unwrap();
+    try_send_packet(&packet_send, p);
+    expect_one_packet(&r0, &dropped);
+    expect_no_packet(&r2);
+}
+
+/// a pdr schedule would replace a custom drop model, so they cannot be combined
+#[test]
+fn custom_drop_model() {
+    let always_drop = || PdrSchedule::piecewise(vec![(Duration::ZERO, 1.0)]).unwrap();
+
+    let line = Line::new(|drone| drone.with_drop_model(EveryNth::new(0)));
+    assert!(matches!(
+        line.drone.with_pdr_schedule(always_drop()),
+        Err(DroneError::InvalidPdrSchedule(_))
+    ));
+
+    // the drop model given last removes the schedule
+    let mut line = Line::new(|drone| {
+        drone
+            .with_pdr_schedule(always_drop())
+            .unwrap()
+            .with_drop_model(EveryNth::new(0))
+    });
+    line.drone.process_packet(fragment(vec![0, 1, 2], 0)).unwrap();
+    assert_eq!(line.r2.try_recv().unwrap(), forwarded(vec![0, 1, 2], 0));
+}
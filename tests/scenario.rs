@@ -0,0 +1,138 @@
+use null_pointer_drone::{
+    scenario::{Scenario, ScenarioError},
+    ControllerError,
+};
+
+/// client 10 - drone 1 - drones 2, 3 - server 20
+const SCENARIO: &str = r#"
+duration = 0.5
+seed = 7
+
+[[drone]]
+id = 1
+connected_node_ids = [10, 2, 3]
+pdr = 0.0
+
+[[drone]]
+id = 2
+connected_node_ids = [1, 20]
+pdr = 0.0
+
+[[drone]]
+id = 3
+connected_node_ids = [1, 20]
+pdr = 0.0
+
+[[client]]
+id = 10
+connected_drone_ids = [1]
+
+[[server]]
+id = 20
+connected_drone_ids = [2, 3]
+
+[[step]]
+at = 0.0
+action = "flood"
+from = 10
+
+[[step]]
+at = 0.1
+action = "crash"
+drone = 2
+
+[[step]]
+at = 0.2
+action = "send"
+from = 10
+hops = [10, 1, 2, 20]
+
+[[step]]
+at = 0.2
+action = "send"
+from = 10
+hops = [10, 1, 3, 20]
+
+[[expect]]
+node = 10
+receives = "flood_response"
+containing = 20
+within = 0.1
+
+[[expect]]
+node = 10
+receives = "nack"
+
+[[expect]]
+node = 20
+receives = "fragment"
+containing = 3
+
+[[expect]]
+node = 10
+receives = "ack"
+containing = 3
+
+[[expect]]
+node = 20
+receives = "fragment"
+containing = 2
+"#;
+
+#[test_log::test]
+fn report_per_expectation() {
+    let scenario = Scenario::parse(SCENARIO).unwrap();
+    assert_eq!(scenario.steps.len(), 4);
+    let report = scenario.run().unwrap();
+
+    let passed: Vec<bool> = report
+        .results
+        .iter()
+        .map(|result| result.passed())
+        .collect();
+    assert_eq!(passed, vec![true, true, true, true, false], "{report}");
+    assert!(!report.passed());
+    assert!(report.panicked.is_empty());
+    assert_eq!(report.stats.len(), 3);
+    assert!(report
+        .to_string()
+        .ends_with("FAIL node 20 receives a fragment containing 2\n4/5 expectations met"));
+}
+
+#[test]
+fn invalid_scenarios() {
+    assert!(matches!(
+        Scenario::parse("[[step]]\nat = -1.0\naction = \"crash\"\ndrone = 1\n"),
+        Err(ScenarioError::InvalidTime(_))
+    ));
+    assert!(matches!(
+        Scenario::parse("[[step]]\naction = \"explode\"\n"),
+        Err(ScenarioError::Parse(_))
+    ));
+    assert!(matches!(
+        Scenario::parse("topology = \"does-not-exist.toml\"\n"),
+        Err(ScenarioError::Io(_))
+    ));
+
+    // the link steps are checked against the topology when the scenario is loaded
+    let self_link = format!("{SCENARIO}[[step]]\nat = 0.3\naction = \"add_link\"\na = 3\nb = 3\n");
+    assert!(matches!(
+        Scenario::parse(&self_link),
+        Err(ScenarioError::Controller(ControllerError::SelfLink(3)))
+    ));
+    // the link 1-2 is gone with the crash of 2
+    let unknown_link =
+        format!("{SCENARIO}[[step]]\nat = 0.3\naction = \"remove_link\"\na = 1\nb = 2\n");
+    assert!(matches!(
+        Scenario::parse(&unknown_link),
+        Err(ScenarioError::Controller(ControllerError::UnknownLink(
+            1, 2
+        )))
+    ));
+
+    let unknown_host = SCENARIO.replace("from = 10", "from = 1");
+    assert!(matches!(
+        Scenario::parse(&unknown_host).unwrap().run(),
+        Err(ScenarioError::UnknownHost(1))
+    ));
+}
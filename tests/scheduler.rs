@@ -0,0 +1,58 @@
+use std::num::NonZeroU32;
+
+use common::{line::Line, packetbuilder::PacketBuilder, packets::fragment};
+use null_pointer_drone::{ClassWeights, Scheduler};
+
+pub mod common;
+
+/// Sends 3 fragments and then 8 acks to 2 through a drone whose channel to 2 has room for a single
+/// packet, and returns the session ids in the order 2 receives them: the fragments have session
+/// ids 1 to 3 and the acks 10 to 17
+fn received_order(scheduler: Scheduler) -> Vec<u64> {
+    let mut line = Line::bounded(1, |drone| drone.with_scheduler(scheduler));
+
+    // the first fragment fills the channel and the other packets wait in the outbound queue
+    for session_id in 1..=3 {
+        line.drone
+            .process_packet(fragment(vec![0, 1, 2], session_id))
+            .unwrap();
+    }
+    for session_id in 10..=17 {
+        let ack = PacketBuilder::new_ack(vec![0, 1, 2])
+            .session_id(session_id)
+            .build();
+        line.drone.process_packet(ack).unwrap();
+    }
+
+    // every packet taken from the channel makes room for the next one, which the drone sends
+    // when it retries its queue
+    let mut received = Vec::new();
+    while let Ok(packet) = line.r2.try_recv() {
+        received.push(packet.session_id);
+        line.drone.step();
+    }
+    received
+}
+
+#[test_log::test]
+fn strict_priority() {
+    assert_eq!(
+        received_order(Scheduler::StrictPriority),
+        vec![1, 10, 11, 12, 13, 14, 15, 16, 17, 2, 3]
+    );
+}
+
+/// with the same weights, every turn the fragments send a full fragment and the acks as many
+/// bytes, 5 acks
+#[test_log::test]
+fn weighted_fair() {
+    let weights = ClassWeights {
+        control: NonZeroU32::MIN,
+        discovery: NonZeroU32::MIN,
+        data: NonZeroU32::MIN,
+    };
+    assert_eq!(
+        received_order(Scheduler::WeightedFair(weights)),
+        vec![1, 2, 10, 11, 12, 13, 14, 3, 15, 16, 17]
+    );
+}
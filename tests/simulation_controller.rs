@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use common::packetbuilder::PacketBuilder;
+use null_pointer_drone::{network, ControllerError, SimulationController};
+use wg_2024::{controller::DroneEvent, network::NodeId};
+
+pub mod common;
+
+/// client 10 - drones 1, 2 - server 20, with the drones also connected to each other
+const TOPOLOGY: &str = r"
+[[drone]]
+id = 1
+connected_node_ids = [2, 10, 20]
+pdr = 0.0
+
+[[drone]]
+id = 2
+connected_node_ids = [1, 10, 20]
+pdr = 0.0
+
+[[client]]
+id = 10
+connected_drone_ids = [1, 2]
+
+[[server]]
+id = 20
+connected_drone_ids = [1, 2]
+";
+
+/// polls the events of the drones until one matches `expected`
+fn wait_for_event(
+    controller: &mut SimulationController,
+    expected: impl Fn(&(NodeId, DroneEvent)) -> bool,
+) {
+    for _ in 0..50 {
+        if controller
+            .process_events_timeout(Duration::from_millis(20))
+            .iter()
+            .any(&expected)
+        {
+            return;
+        }
+    }
+    panic!("the expected event never arrived");
+}
+
+#[test_log::test]
+fn shortcut_is_delivered() {
+    let config = network::parse(TOPOLOGY).unwrap();
+    let (mut network, _threads) = network::start(&config).unwrap();
+    let mut controller = SimulationController::new(&mut network, &config);
+    assert!(network.drones.is_empty());
+
+    controller.remove_link(1, 10).unwrap();
+    assert_eq!(controller.neighbors(1), vec![2, 20]);
+    // drone 1 would fail on these commands, it is still running below since they are never sent
+    assert_eq!(
+        controller.remove_link(1, 10),
+        Err(ControllerError::UnknownLink(1, 10))
+    );
+    assert_eq!(controller.add_link(1, 1), Err(ControllerError::SelfLink(1)));
+    network.servers[&20].neighbors[&1]
+        .send(PacketBuilder::new_ack(vec![20, 1, 10]).build())
+        .unwrap();
+
+    wait_for_event(&mut controller, |(id, event)| {
+        *id == 1 && matches!(event, DroneEvent::ControllerShortcut(_))
+    });
+    let delivered = network.clients[&10]
+        .packet_recv
+        .recv_timeout(Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(delivered.routing_header.hops, vec![20, 1, 10]);
+}
+
+#[test_log::test]
+fn crash_and_pdr() {
+    let config = network::parse(TOPOLOGY).unwrap();
+    let (mut network, mut threads) = network::start(&config).unwrap();
+    let mut controller = SimulationController::new(&mut network, &config);
+
+    assert_eq!(
+        controller.set_pdr(2, 1.5),
+        Err(ControllerError::InvalidPdr(1.5))
+    );
+    controller.set_pdr_everywhere(1.0).unwrap();
+    network.clients[&10].neighbors[&2]
+        .send(PacketBuilder::new_fragment(vec![10, 2, 20]).build())
+        .unwrap();
+    wait_for_event(&mut controller, |(id, event)| {
+        *id == 2 && matches!(event, DroneEvent::PacketDropped(_))
+    });
+
+    controller.crash(1).unwrap();
+    assert_eq!(controller.neighbors(2), vec![10, 20]);
+    assert_eq!(controller.crash(1), Err(ControllerError::UnknownDrone(1)));
+    assert_eq!(
+        controller.add_link(1, 2),
+        Err(ControllerError::UnknownNode(1))
+    );
+
+    // the hosts drop their senders to the crashed drone, then it stops
+    for host in network
+        .clients
+        .values_mut()
+        .chain(network.servers.values_mut())
+    {
+        host.neighbors.remove(&1);
+    }
+    threads.remove(0).join().unwrap();
+}
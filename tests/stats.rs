@@ -0,0 +1,140 @@
+use std::{collections::HashMap, time::Duration};
+
+use common::{
+    create_channels, expect::try_send_packet, packetbuilder::PacketBuilder, start_drone_thread,
+    RECV_WAIT_TIME,
+};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::{
+    DroneStats, LinkStats, MyDrone, NackCounts, PacketCounts, State, MAX_PACKET_SIZE,
+};
+use wg_2024::{
+    drone::Drone,
+    packet::{NackType, NodeType, Packet},
+};
+
+pub mod common;
+
+/// topology: 0-1-2 and 1-3, the link 1-3 drops every fragment
+/// the stats are read from the test thread while the drone is running
+#[test_log::test]
+fn stats_snapshot() {
+    let (event_send, event_recv, _command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+
+    let (s0, _r0) = unbounded::<Packet>();
+    let (s2, _r2) = unbounded::<Packet>();
+    let (s3, _r3) = unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, s0);
+    senders.insert(2, s2);
+    senders.insert(3, s3);
+
+    let mut my_drone = MyDrone::new(1, event_send, command_recv, packet_recv, senders, 0.0);
+    my_drone.set_neighbor_pdr(3, Some(1.0)).unwrap();
+    let stats = my_drone.stats_handle();
+    let _handle = start_drone_thread(my_drone);
+
+    assert_eq!(
+        stats.snapshot(),
+        DroneStats {
+            links: HashMap::from([
+                (0, LinkStats::default()),
+                (2, LinkStats::default()),
+                (3, LinkStats::default()),
+            ]),
+            ..DroneStats::default()
+        }
+    );
+
+    let flood_request = PacketBuilder::new_floodreq(vec![(0, NodeType::Client)]).build();
+    for packet in [
+        // dropped, then nacked
+        PacketBuilder::new_fragment(vec![0, 1, 3]).build(),
+        // forwarded
+        PacketBuilder::new_fragment(vec![0, 1, 2]).build(),
+        // shortcut, 4 is not a neighbor
+        PacketBuilder::new_ack(vec![0, 1, 4]).build(),
+        // nacked, the drone is the destination
+        PacketBuilder::new_fragment(vec![0, 1]).build(),
+        // forwarded to 2 and 3
+        flood_request.clone(),
+        // answered, already seen
+        flood_request,
+    ] {
+        try_send_packet(&packet_send, packet);
+    }
+
+    // every counter is updated before the event about the same packet is sent
+    for _ in 0..8 {
+        event_recv
+            .recv_timeout(Duration::from_millis(RECV_WAIT_TIME))
+            .unwrap();
+    }
+
+    assert_eq!(
+        stats.snapshot(),
+        DroneStats {
+            state: State::Working,
+            pdr: 0.0,
+            received: PacketCounts {
+                fragment: 3,
+                ack: 1,
+                flood_request: 2,
+                ..PacketCounts::default()
+            },
+            forwarded: PacketCounts {
+                fragment: 1,
+                nack: 2,
+                flood_request: 2,
+                flood_response: 1,
+                ..PacketCounts::default()
+            },
+            dropped: PacketCounts {
+                fragment: 1,
+                ..PacketCounts::default()
+            },
+            nacked: NackCounts {
+                dropped: 1,
+                destination_is_drone: 1,
+                ..NackCounts::default()
+            },
+            shortcut: PacketCounts {
+                ack: 1,
+                ..PacketCounts::default()
+            },
+            flood_requests_forwarded: 1,
+            flood_requests_answered: 1,
+            flood_requests_rate_limited: 0,
+            flood_cache_len: 1,
+            flood_cache_evictions: 0,
+            links: HashMap::from([
+                (
+                    0,
+                    LinkStats {
+                        packets: 3,
+                        ..LinkStats::default()
+                    }
+                ),
+                (
+                    2,
+                    LinkStats {
+                        packets: 2,
+                        bytes: 128,
+                        ..LinkStats::default()
+                    }
+                ),
+                (
+                    3,
+                    LinkStats {
+                        packets: 1,
+                        drops: 1,
+                        dropped_wire_bytes: u64::from(MAX_PACKET_SIZE),
+                        ..LinkStats::default()
+                    }
+                ),
+            ]),
+        }
+    );
+    assert_eq!(stats.snapshot().nacked.get(NackType::DestinationIsDrone), 1);
+}
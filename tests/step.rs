@@ -0,0 +1,98 @@
+use std::{collections::HashMap, time::Duration};
+
+use common::{
+    create_channels,
+    expect::{expect_one_packet, try_send_command, try_send_packet},
+    packetbuilder::PacketBuilder,
+};
+use crossbeam_channel::unbounded;
+use null_pointer_drone::{
+    ControlCommand, DelayModel, LinkDelay, MockClock, MyDrone, State, StepOutcome,
+};
+use wg_2024::{
+    controller::DroneCommand,
+    packet::{NackType, Packet},
+};
+
+pub mod common;
+
+/// topology: 0-1-2
+#[test_log::test]
+fn step_in_priority_order() {
+    let (event_send, _event_recv, command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+    let (s0, r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let senders = HashMap::from([(0, s0), (2, s2)]);
+    let mut my_drone = MyDrone::builder(1, event_send, command_recv, packet_recv, senders)
+        .build()
+        .unwrap();
+    let control_send = my_drone.control_sender();
+
+    let fragment = PacketBuilder::new_fragment(vec![0, 1, 2]).build();
+    try_send_packet(&packet_send, fragment.clone());
+    control_send
+        .send(ControlCommand::SetNeighborPdr(2, None))
+        .unwrap();
+    try_send_command(&command_send, DroneCommand::SetPacketDropRate(1.0));
+
+    assert!(matches!(
+        my_drone.step(),
+        StepOutcome::Command(DroneCommand::SetPacketDropRate(_))
+    ));
+    assert!(matches!(
+        my_drone.step(),
+        StepOutcome::Control(ControlCommand::SetNeighborPdr(2, None))
+    ));
+    assert!(matches!(my_drone.step(), StepOutcome::Packet(packet) if packet == fragment));
+    expect_one_packet(
+        &r0,
+        &PacketBuilder::new_nack(vec![1, 0], NackType::Dropped).build(),
+    );
+    assert!(r2.is_empty());
+    assert!(matches!(my_drone.step(), StepOutcome::Idle));
+
+    try_send_command(&command_send, DroneCommand::Crash);
+    assert!(matches!(
+        my_drone.step(),
+        StepOutcome::Command(DroneCommand::Crash)
+    ));
+    assert_eq!(my_drone.stats().state, State::Crashing);
+    drop(packet_send);
+    assert!(matches!(my_drone.step(), StepOutcome::Finished));
+}
+
+/// topology: 0-1-2, the link to 2 has a delay of 100ms
+#[test_log::test]
+fn step_releases_delayed_packets() {
+    let (event_send, _event_recv, _command_send, command_recv, packet_send, packet_recv) =
+        create_channels();
+    let (s0, _r0) = unbounded::<Packet>();
+    let (s2, r2) = unbounded::<Packet>();
+    let senders = HashMap::from([(0, s0), (2, s2)]);
+    let clock = MockClock::new();
+    let delay = LinkDelay::new(DelayModel::Fixed(Duration::from_millis(100)));
+    let mut my_drone = MyDrone::builder(1, event_send, command_recv, packet_recv, senders)
+        .clock(clock.clone())
+        .neighbor_delay(2, delay)
+        .build()
+        .unwrap();
+
+    try_send_packet(
+        &packet_send,
+        PacketBuilder::new_fragment(vec![0, 1, 2]).build(),
+    );
+    assert!(matches!(my_drone.step(), StepOutcome::Packet(_)));
+    assert!(matches!(my_drone.step(), StepOutcome::Idle));
+    assert!(r2.is_empty());
+
+    clock.advance(Duration::from_millis(100));
+    assert!(matches!(my_drone.step(), StepOutcome::Timer));
+    expect_one_packet(
+        &r2,
+        &PacketBuilder::new_fragment(vec![0, 1, 2])
+            .hop_index(2)
+            .build(),
+    );
+    assert!(matches!(my_drone.step(), StepOutcome::Idle));
+}
@@ -0,0 +1,143 @@
+use std::process::Command;
+
+use null_pointer_drone::{network, validate_topology, TopologyIssue};
+
+/// drones 1 to 5, clients 10 to 12, servers 20 and 21, with every kind of problem
+const BROKEN_TOPOLOGY: &str = r"
+[[drone]]
+id = 1
+connected_node_ids = [1, 2, 10, 11, 20]
+pdr = 0.1
+
+[[drone]]
+id = 2
+connected_node_ids = [1, 10, 11, 20, 99]
+pdr = 2.0
+
+[[drone]]
+id = 3
+connected_node_ids = [2, 4, 11]
+pdr = 0.0
+
+[[drone]]
+id = 4
+connected_node_ids = [3, 21]
+pdr = 0.0
+
+[[drone]]
+id = 5
+connected_node_ids = []
+pdr = 0.0
+
+[[client]]
+id = 10
+connected_drone_ids = [1, 2, 12]
+
+[[client]]
+id = 11
+connected_drone_ids = [1, 2, 3]
+
+[[client]]
+id = 12
+connected_drone_ids = [10]
+
+[[server]]
+id = 20
+connected_drone_ids = [1, 2]
+
+[[server]]
+id = 21
+connected_drone_ids = [4]
+
+[[server]]
+id = 5
+connected_drone_ids = []
+";
+
+/// client 10 - drones 1, 2 - server 20
+const VALID_TOPOLOGY: &str = r"
+[[drone]]
+id = 1
+connected_node_ids = [2, 10, 20]
+pdr = 0.0
+
+[[drone]]
+id = 2
+connected_node_ids = [1, 20]
+pdr = 0.0
+
+[[client]]
+id = 10
+connected_drone_ids = [1]
+
+[[server]]
+id = 20
+connected_drone_ids = [1, 2]
+";
+
+#[test]
+fn every_issue_is_reported() {
+    let config = network::parse(BROKEN_TOPOLOGY).unwrap();
+    assert_eq!(
+        validate_topology(&config),
+        vec![
+            TopologyIssue::DuplicateId(5),
+            TopologyIssue::SelfLink(1),
+            TopologyIssue::UnknownNode {
+                node: 2,
+                neighbor: 99
+            },
+            TopologyIssue::AsymmetricLink {
+                node: 3,
+                neighbor: 2
+            },
+            TopologyIssue::NotADrone {
+                host: 10,
+                neighbor: 12
+            },
+            TopologyIssue::NotADrone {
+                host: 12,
+                neighbor: 10
+            },
+            TopologyIssue::InvalidPdr { drone: 2, pdr: 2.0 },
+            TopologyIssue::TooManyDrones {
+                client: 11,
+                drones: 3
+            },
+            TopologyIssue::TooFewDrones {
+                server: 21,
+                drones: 1
+            },
+            TopologyIssue::Isolated(5),
+            TopologyIssue::Partitioned(vec![vec![1, 2, 3, 4, 10, 11, 20, 21], vec![12]]),
+        ]
+    );
+}
+
+#[test]
+fn command_line() {
+    let dir = std::env::temp_dir().join(format!("npd-validate-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let broken = dir.join("broken.toml");
+    std::fs::write(&broken, BROKEN_TOPOLOGY).unwrap();
+    let valid = dir.join("valid.toml");
+    std::fs::write(&valid, VALID_TOPOLOGY).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_npd-validate"))
+        .arg(&valid)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_npd-validate"))
+        .arg(&valid)
+        .arg(&broken)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("broken.toml: 11 problems"));
+    assert!(stdout.contains("- node 1 is connected to itself"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}